@@ -0,0 +1,426 @@
+use std::fmt;
+
+use crate::game::{diplomacy::TreatyKind, market::OrderSide};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Help,
+    NewGame {
+        planets: usize,
+        seed: Option<u64>,
+    },
+    Join {
+        planet: Option<String>,
+    },
+    Status {
+        planet: Option<String>,
+    },
+    Build {
+        planet: String,
+        building: String,
+    },
+    Upgrade {
+        planet: String,
+        index: usize,
+    },
+    Fleets {
+        planet: String,
+    },
+    CreateFleet {
+        planet: String,
+        name: String,
+    },
+    RenameFleet {
+        fleet: u32,
+        name: String,
+    },
+    MergeFleets {
+        from: u32,
+        into: u32,
+    },
+    SplitFleet {
+        fleet: u32,
+        count: u32,
+        unit: String,
+        name: String,
+    },
+    DisbandFleet {
+        fleet: u32,
+    },
+    Rally {
+        planet: String,
+        index: usize,
+        fleet: Option<u32>,
+    },
+    Move {
+        planet: String,
+        target: String,
+        fleet: String,
+    },
+    Research {
+        tech: Option<String>,
+    },
+    Refit {
+        planet: String,
+        index: usize,
+        blueprint: String,
+    },
+    TradeRoutes,
+    CreateTradeRoute {
+        origin: String,
+        destination: String,
+        interval: u32,
+        cargo: Vec<(i32, String)>,
+    },
+    CancelTradeRoute {
+        id: u32,
+    },
+    Orders,
+    MarketPrices {
+        resource: String,
+    },
+    PlaceOrder {
+        side: OrderSide,
+        planet: String,
+        quantity: i32,
+        resource: String,
+        price: i32,
+    },
+    CancelOrder {
+        id: u32,
+    },
+    Diplomacy,
+    Propose {
+        kind: TreatyKind,
+        player: String,
+        duration: Option<u32>,
+    },
+    Accept {
+        id: u32,
+    },
+    Reject {
+        id: u32,
+    },
+    DeclareWar {
+        player: String,
+    },
+    Tick,
+    Events,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    NotACommand,
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidArgument(&'static str, String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotACommand => write!(f, "Commands start with /"),
+            Self::UnknownCommand(name) => {
+                write!(f, "Unknown command /{}, try /help", name)
+            }
+            Self::MissingArgument(argument) => write!(f, "Missing <{}>", argument),
+            Self::InvalidArgument(argument, value) => {
+                write!(f, "\"{}\" is not a valid <{}>", value, argument)
+            }
+        }
+    }
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        let text = match text.strip_prefix('/') {
+            Some(text) => text,
+            None => return Err(ParseError::NotACommand),
+        };
+
+        let mut words = text.split_whitespace();
+        let name = words.next().unwrap_or_default().to_lowercase();
+
+        match name.as_str() {
+            "help" => Ok(Self::Help),
+            "newgame" => {
+                let planets = next_argument(&mut words, "planets")?;
+                let planets = match planets.parse() {
+                    Ok(planets) if planets > 0 => planets,
+                    _ => return Err(ParseError::InvalidArgument("planets", planets)),
+                };
+                let seed = match words.next() {
+                    None => None,
+                    Some(seed) => match seed.parse() {
+                        Ok(seed) => Some(seed),
+                        Err(_) => {
+                            return Err(ParseError::InvalidArgument("seed", seed.to_string()))
+                        }
+                    },
+                };
+                Ok(Self::NewGame { planets, seed })
+            }
+            "join" => Ok(Self::Join {
+                planet: words.next().map(String::from),
+            }),
+            "status" => Ok(Self::Status {
+                planet: words.next().map(String::from),
+            }),
+            "build" => Ok(Self::Build {
+                planet: next_argument(&mut words, "planet")?,
+                building: next_argument(&mut words, "building")?,
+            }),
+            "upgrade" => {
+                let planet = next_argument(&mut words, "planet")?;
+                let index = next_argument(&mut words, "building #")?;
+                match index.parse() {
+                    Ok(index) => Ok(Self::Upgrade { planet, index }),
+                    Err(_) => Err(ParseError::InvalidArgument("building #", index)),
+                }
+            }
+            "fleet" => {
+                let planet = next_argument(&mut words, "planet")?;
+                match planet.as_str() {
+                    "rename" => {
+                        let fleet = next_id(&mut words, "fleet #")?;
+                        let name = words.collect::<Vec<_>>().join(" ");
+                        if name.is_empty() {
+                            return Err(ParseError::MissingArgument("fleet name"));
+                        }
+                        return Ok(Self::RenameFleet { fleet, name });
+                    }
+                    "merge" => {
+                        return Ok(Self::MergeFleets {
+                            from: next_id(&mut words, "fleet #")?,
+                            into: next_id(&mut words, "target fleet #")?,
+                        })
+                    }
+                    "split" => {
+                        let fleet = next_id(&mut words, "fleet #")?;
+                        let count = next_argument(&mut words, "count")?;
+                        let count = match count.parse() {
+                            Ok(count) if count > 0 => count,
+                            _ => return Err(ParseError::InvalidArgument("count", count)),
+                        };
+                        let unit = next_argument(&mut words, "unit")?;
+                        let name = words.collect::<Vec<_>>().join(" ");
+                        if name.is_empty() {
+                            return Err(ParseError::MissingArgument("fleet name"));
+                        }
+                        return Ok(Self::SplitFleet {
+                            fleet,
+                            count,
+                            unit,
+                            name,
+                        });
+                    }
+                    "disband" => {
+                        return Ok(Self::DisbandFleet {
+                            fleet: next_id(&mut words, "fleet #")?,
+                        })
+                    }
+                    _ => (),
+                }
+                match words.next() {
+                    None => Ok(Self::Fleets { planet }),
+                    Some("create") => {
+                        let name = words.collect::<Vec<_>>().join(" ");
+                        if name.is_empty() {
+                            return Err(ParseError::MissingArgument("fleet name"));
+                        }
+                        Ok(Self::CreateFleet { planet, name })
+                    }
+                    Some(action) => Err(ParseError::InvalidArgument(
+                        "fleet action",
+                        action.to_string(),
+                    )),
+                }
+            }
+            "rally" => {
+                let planet = next_argument(&mut words, "planet")?;
+                let index = next_argument(&mut words, "building #")?;
+                let index = match index.parse() {
+                    Ok(index) => index,
+                    Err(_) => return Err(ParseError::InvalidArgument("building #", index)),
+                };
+                let fleet = match words.next() {
+                    None => None,
+                    Some(fleet) => match fleet.trim_start_matches('#').parse() {
+                        Ok(fleet) => Some(fleet),
+                        Err(_) => {
+                            return Err(ParseError::InvalidArgument("fleet #", fleet.to_string()))
+                        }
+                    },
+                };
+                Ok(Self::Rally {
+                    planet,
+                    index,
+                    fleet,
+                })
+            }
+            "move" => {
+                let planet = next_argument(&mut words, "planet")?;
+                let target = next_argument(&mut words, "target planet")?;
+                let fleet = words.collect::<Vec<_>>().join(" ");
+                if fleet.is_empty() {
+                    return Err(ParseError::MissingArgument("fleet"));
+                }
+                Ok(Self::Move {
+                    planet,
+                    target,
+                    fleet,
+                })
+            }
+            "research" => Ok(Self::Research {
+                tech: words.next().map(String::from),
+            }),
+            "refit" => {
+                let planet = next_argument(&mut words, "planet")?;
+                let index = next_argument(&mut words, "building #")?;
+                let index = match index.parse() {
+                    Ok(index) => index,
+                    Err(_) => return Err(ParseError::InvalidArgument("building #", index)),
+                };
+                Ok(Self::Refit {
+                    planet,
+                    index,
+                    blueprint: next_argument(&mut words, "blueprint")?,
+                })
+            }
+            "trade" => {
+                let origin = match words.next() {
+                    None => return Ok(Self::TradeRoutes),
+                    Some(origin) => origin.to_string(),
+                };
+                if origin == "cancel" {
+                    let id = next_argument(&mut words, "route #")?;
+                    return match id.trim_start_matches('#').parse() {
+                        Ok(id) => Ok(Self::CancelTradeRoute { id }),
+                        Err(_) => Err(ParseError::InvalidArgument("route #", id)),
+                    };
+                }
+                let destination = next_argument(&mut words, "destination")?;
+                let interval = next_argument(&mut words, "every")?;
+                let interval = match interval.parse() {
+                    Ok(interval) if interval > 0 => interval,
+                    _ => return Err(ParseError::InvalidArgument("every", interval)),
+                };
+                let mut cargo = vec![];
+                while let Some(amount) = words.next() {
+                    let amount = match amount.parse() {
+                        Ok(amount) if amount > 0 => amount,
+                        _ => return Err(ParseError::InvalidArgument("amount", amount.to_string())),
+                    };
+                    cargo.push((amount, next_argument(&mut words, "resource")?));
+                }
+                if cargo.is_empty() {
+                    return Err(ParseError::MissingArgument("amount"));
+                }
+                Ok(Self::CreateTradeRoute {
+                    origin,
+                    destination,
+                    interval,
+                    cargo,
+                })
+            }
+            "market" => match words.next() {
+                None => Ok(Self::Orders),
+                Some("cancel") => {
+                    let id = next_argument(&mut words, "order #")?;
+                    match id.trim_start_matches('#').parse() {
+                        Ok(id) => Ok(Self::CancelOrder { id }),
+                        Err(_) => Err(ParseError::InvalidArgument("order #", id)),
+                    }
+                }
+                Some(resource) => Ok(Self::MarketPrices {
+                    resource: resource.to_string(),
+                }),
+            },
+            "buy" | "sell" => {
+                let side = if name == "buy" {
+                    OrderSide::Buy
+                } else {
+                    OrderSide::Sell
+                };
+                let planet = next_argument(&mut words, "planet")?;
+                let quantity = next_argument(&mut words, "amount")?;
+                let quantity = match quantity.parse() {
+                    Ok(quantity) if quantity > 0 => quantity,
+                    _ => return Err(ParseError::InvalidArgument("amount", quantity)),
+                };
+                let resource = next_argument(&mut words, "resource")?;
+                let price = next_argument(&mut words, "price")?;
+                let price = match price.parse() {
+                    Ok(price) if price > 0 => price,
+                    _ => return Err(ParseError::InvalidArgument("price", price)),
+                };
+                Ok(Self::PlaceOrder {
+                    side,
+                    planet,
+                    quantity,
+                    resource,
+                    price,
+                })
+            }
+            "diplomacy" => Ok(Self::Diplomacy),
+            "propose" => {
+                let kind = next_argument(&mut words, "alliance|pact")?;
+                let kind = match kind.to_lowercase().as_str() {
+                    "alliance" => TreatyKind::Alliance,
+                    "pact" => TreatyKind::NonAggression,
+                    _ => return Err(ParseError::InvalidArgument("alliance|pact", kind)),
+                };
+                let player = next_argument(&mut words, "player")?;
+                let duration = match words.next() {
+                    None => None,
+                    Some(ticks) => match ticks.parse() {
+                        Ok(ticks) if ticks > 0 => Some(ticks),
+                        _ => return Err(ParseError::InvalidArgument("ticks", ticks.to_string())),
+                    },
+                };
+                Ok(Self::Propose {
+                    kind,
+                    player,
+                    duration,
+                })
+            }
+            "accept" | "reject" => {
+                let id = next_argument(&mut words, "proposal #")?;
+                match id.trim_start_matches('#').parse() {
+                    Ok(id) if name == "accept" => Ok(Self::Accept { id }),
+                    Ok(id) => Ok(Self::Reject { id }),
+                    Err(_) => Err(ParseError::InvalidArgument("proposal #", id)),
+                }
+            }
+            "war" => Ok(Self::DeclareWar {
+                player: next_argument(&mut words, "player")?,
+            }),
+            "tick" => Ok(Self::Tick),
+            "events" => Ok(Self::Events),
+            _ => Err(ParseError::UnknownCommand(name)),
+        }
+    }
+}
+
+fn next_argument<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+    argument: &'static str,
+) -> Result<String, ParseError> {
+    words
+        .next()
+        .map(String::from)
+        .ok_or(ParseError::MissingArgument(argument))
+}
+
+/// An id written as `3` or `#3`.
+fn next_id<'a>(
+    words: &mut impl Iterator<Item = &'a str>,
+    argument: &'static str,
+) -> Result<u32, ParseError> {
+    let id = next_argument(words, argument)?;
+    match id.trim_start_matches('#').parse() {
+        Ok(id) => Ok(id),
+        Err(_) => Err(ParseError::InvalidArgument(argument, id)),
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::VecDeque;
+
+use crate::game::player::PlayerId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncomingMessage {
+    pub channel: u64,
+    pub author: PlayerId,
+    pub author_name: String,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbedField {
+    pub name: String,
+    pub value: String,
+    pub inline: bool,
+}
+
+/// A rich message block, mapped to a Discord embed by the bot's gateway.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Embed {
+    pub title: String,
+    pub description: String,
+    pub fields: Vec<EmbedField>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutgoingMessage {
+    pub channel: u64,
+    pub content: String,
+    pub embeds: Vec<Embed>,
+}
+
+/// Where commands come from and replies go to. Only the console and mock gateways
+/// live in this crate, the Discord gateway belongs to the bot embedding it.
+pub trait Gateway {
+    fn receive(&mut self) -> Option<IncomingMessage>;
+    fn send(&mut self, message: OutgoingMessage);
+}
+
+/// An in-memory gateway, messages are queued by hand and replies are kept in `sent`.
+#[derive(Debug, Default)]
+pub struct MockGateway {
+    pub incoming: VecDeque<IncomingMessage>,
+    pub sent: Vec<OutgoingMessage>,
+}
+
+impl MockGateway {
+    pub fn push(&mut self, channel: u64, author: PlayerId, author_name: &str, content: &str) {
+        self.incoming.push_back(IncomingMessage {
+            channel,
+            author,
+            author_name: author_name.to_string(),
+            content: content.to_string(),
+        });
+    }
+
+    pub fn take_sent(&mut self) -> Vec<OutgoingMessage> {
+        std::mem::take(&mut self.sent)
+    }
+}
+
+impl Gateway for MockGateway {
+    fn receive(&mut self) -> Option<IncomingMessage> {
+        self.incoming.pop_front()
+    }
+
+    fn send(&mut self, message: OutgoingMessage) {
+        self.sent.push(message);
+    }
+}
@@ -0,0 +1,1401 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::game::{
+    buildings::Building,
+    combat::{BattleOutcome, BattleReport},
+    diplomacy::{Relation, Treaty},
+    error::GameError,
+    galaxy::{generate_galaxy, GalaxyConfig},
+    game_world::GameWorld,
+    market::{Order, PricePoint},
+    planet::Planet,
+    player::PlayerId,
+    research::TechStatus,
+    resource::{Resource, ResourceType},
+    resource_registry::ResourceRegistry,
+    tickable::{TickResult, Tickable},
+};
+
+use super::{
+    command::Command,
+    gateway::{Embed, EmbedField, Gateway, IncomingMessage, OutgoingMessage},
+};
+
+const HELP: &str = "Commands:
+/newgame <planets> [seed] - create a new galaxy before anyone has joined
+/join [planet] - join the game and claim a free planet, or get a home planet
+/status [planet] - show your planets or one planet in detail
+/build <planet> <building> - build a building from the catalog
+/upgrade <planet> <building #> - upgrade a building
+/fleet <planet> [create <name>] - list or create fleets
+/fleet rename <fleet #> <name> - rename a fleet
+/fleet merge <fleet #> <target fleet #> - move every ship of a fleet into another one
+/fleet split <fleet #> <count> <unit> <name> - move ships of one kind into a new fleet
+/fleet disband <fleet #> - remove an empty fleet
+/rally <planet> <building #> [fleet #] - pick the fleet a building's ships join
+/move <planet> <target planet> <fleet> - send a fleet to another planet
+/research [tech] - list the tech tree or start researching a tech
+/refit <planet> <building #> <blueprint> - switch what a military building builds
+/trade [<planet> <destination> <every> <amount> <resource>...] - list trade routes or ship resources every few ticks
+/trade cancel <route #> - stop a trade route
+/buy <planet> <amount> <resource> <price> - offer Credits for a resource on the market
+/sell <planet> <amount> <resource> <price> - offer a resource for Credits on the market
+/market [<resource> | cancel <order #>] - list your orders, show a resource's prices or cancel an order
+/diplomacy - list your relations and treaty proposals
+/propose <alliance|pact> <player> [ticks] - offer a player a treaty, for a number of ticks or for good
+/accept <proposal #> - sign a treaty you were offered
+/reject <proposal #> - decline or withdraw a proposal
+/war <player> - declare war, breaking any treaty with them
+/tick - advance the game by one tick, only for the player running the game
+/events - show what happened to you since you last looked";
+
+/// Price points shown by /market <resource>.
+const PRICE_CHART_POINTS: usize = 10;
+const PRICE_CHART_WIDTH: i32 = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reply {
+    pub content: String,
+    pub embeds: Vec<Embed>,
+}
+
+impl From<String> for Reply {
+    fn from(content: String) -> Self {
+        Self {
+            content,
+            embeds: vec![],
+        }
+    }
+}
+
+/// Answers every message waiting on the gateway.
+pub fn run_pending(world: &mut GameWorld, gateway: &mut impl Gateway) {
+    while let Some(message) = gateway.receive() {
+        let reply = handle_message(world, &message);
+        gateway.send(OutgoingMessage {
+            channel: message.channel,
+            content: reply.content,
+            embeds: reply.embeds,
+        });
+    }
+}
+
+pub fn handle_message(world: &mut GameWorld, message: &IncomingMessage) -> Reply {
+    let command = match Command::parse(&message.content) {
+        Ok(command) => command,
+        Err(err) => return err.to_string().into(),
+    };
+
+    match execute(world, message, command) {
+        Ok(reply) => reply,
+        Err(err) => err.to_string().into(),
+    }
+}
+
+fn execute(
+    world: &mut GameWorld,
+    message: &IncomingMessage,
+    command: Command,
+) -> Result<Reply, GameError> {
+    let player = message.author;
+
+    match command {
+        Command::Help => Ok(String::from(HELP).into()),
+        Command::NewGame { planets, seed } => {
+            if !world.players.is_empty() {
+                return Err(GameError::GameAlreadyStarted);
+            }
+            // without a seed every channel gets its own galaxy
+            let seed = seed.unwrap_or(message.channel);
+            world.planets = generate_galaxy(&GalaxyConfig::new(seed, planets))?;
+            world.admin = Some(player);
+            world.init_planet_resources();
+            world.assign_fleet_ids();
+            world.movements.clear();
+            Ok(format!(
+                "Created a galaxy of {} planets (seed {}), /join to get a home planet",
+                planets, seed
+            )
+            .into())
+        }
+        Command::Join { planet: None } => {
+            world.register_player(player, message.author_name.clone())?;
+            let planet = match world.assign_home_planet(player) {
+                Ok(planet) => planet,
+                Err(err) => {
+                    world.players.remove(&player);
+                    return Err(err);
+                }
+            };
+            Ok(format!(
+                "Welcome {}, your home planet is {}",
+                message.author_name, planet
+            )
+            .into())
+        }
+        Command::Join {
+            planet: Some(planet),
+        } => {
+            if world.players.contains_key(&player) {
+                return Err(GameError::PlayerAlreadyRegistered(player));
+            }
+            if world.get_planet(&planet)?.owner.is_some() {
+                return Err(GameError::PlanetAlreadyOwned(planet));
+            }
+            world.register_player(player, message.author_name.clone())?;
+            world.claim_planet(player, &planet)?;
+            Ok(format!("Welcome {}, {} is now yours", message.author_name, planet).into())
+        }
+        Command::Status { planet: None } => {
+            world.get_player(player)?;
+            let mut lines: Vec<String> = world
+                .planets_of(player)
+                .map(|planet| {
+                    format!(
+                        "{} ({}, {}) - {} buildings, {} in construction",
+                        planet.name,
+                        planet.position.x,
+                        planet.position.y,
+                        planet.buildings.len(),
+                        planet.construction_queue.len()
+                    )
+                })
+                .collect();
+            lines.extend(world.movements_of(player).map(|movement| {
+                format!(
+                    "{}: {} -> {}, {} ticks left",
+                    movement.fleet_name,
+                    movement.origin,
+                    movement.destination,
+                    movement.ticks_remaining()
+                )
+            }));
+            if lines.is_empty() {
+                Ok(String::from("You don't own any planets").into())
+            } else {
+                Ok(lines.join("\n").into())
+            }
+        }
+        Command::Status {
+            planet: Some(planet),
+        } => {
+            world.owned_planet_mut(player, &planet)?;
+            Ok(render_planet(world.get_planet(&planet)?, &world.resources).into())
+        }
+        Command::Build { planet, building } => {
+            world.build_from_catalog(player, &planet, &building)?;
+            Ok(format!("Started building {} on {}", building, planet).into())
+        }
+        Command::Upgrade { planet, index } => {
+            let planet = world.owned_planet_mut(player, &planet)?;
+            planet.upgrade_building(index)?;
+            let building = planet.buildings[index].get_building_data();
+            Ok(format!("{} is now level {}", building.name, building.level).into())
+        }
+        Command::Fleets { planet } => {
+            let planet = world.owned_planet_mut(player, &planet)?;
+            let mut fleets: Vec<_> = planet.military.iter().collect();
+            fleets.sort_by_key(|(_, fleet)| fleet.id);
+            let fleets: Vec<String> = fleets
+                .into_iter()
+                .map(|(name, fleet)| {
+                    let units: Vec<String> = fleet
+                        .unit_counts()
+                        .iter()
+                        .map(|(unit, count)| format!("{}x {}", count, unit))
+                        .collect();
+                    let damaged = fleet
+                        .ships
+                        .iter()
+                        .filter(|ship| ship.get_creation().damage > 0)
+                        .count();
+                    if units.is_empty() {
+                        format!("#{} {}: no units", fleet.id, name)
+                    } else if damaged > 0 {
+                        format!(
+                            "#{} {}: {} units ({}), {} damaged",
+                            fleet.id,
+                            name,
+                            fleet.ships.len(),
+                            units.join(", "),
+                            damaged
+                        )
+                    } else {
+                        format!(
+                            "#{} {}: {} units ({})",
+                            fleet.id,
+                            name,
+                            fleet.ships.len(),
+                            units.join(", ")
+                        )
+                    }
+                })
+                .collect();
+            Ok(fleets.join("\n").into())
+        }
+        Command::CreateFleet { planet, name } => {
+            let id = world.create_fleet(player, &planet, name.clone())?;
+            Ok(format!("Created fleet #{} {} on {}", id, name, planet).into())
+        }
+        Command::RenameFleet { fleet, name } => {
+            world.rename_fleet(player, fleet, name.clone())?;
+            Ok(format!("Fleet #{} is now called {}", fleet, name).into())
+        }
+        Command::MergeFleets { from, into } => {
+            world.merge_fleets(player, from, into)?;
+            Ok(format!("Fleet #{} joined fleet #{}", from, into).into())
+        }
+        Command::SplitFleet {
+            fleet,
+            count,
+            unit,
+            name,
+        } => {
+            let id = world.split_fleet(player, fleet, &unit, count, name.clone())?;
+            Ok(format!(
+                "{} {} left fleet #{} to form fleet #{} {}",
+                count, unit, fleet, id, name
+            )
+            .into())
+        }
+        Command::DisbandFleet { fleet } => {
+            world.disband_fleet(player, fleet)?;
+            Ok(format!("Fleet #{} has been disbanded", fleet).into())
+        }
+        Command::Rally {
+            planet,
+            index,
+            fleet,
+        } => {
+            world.set_rally_fleet(player, &planet, index, fleet)?;
+            match fleet {
+                Some(fleet) => Ok(format!(
+                    "Ships built by building #{} on {} join fleet #{}",
+                    index, planet, fleet
+                )
+                .into()),
+                None => Ok(format!(
+                    "Ships built by building #{} on {} join the planet's fleet",
+                    index, planet
+                )
+                .into()),
+            }
+        }
+        Command::Move {
+            planet,
+            target,
+            fleet,
+        } => {
+            let ticks = world.move_fleet(player, &planet, &fleet, &target)?;
+            Ok(format!("{} will arrive at {} in {} ticks", fleet, target, ticks).into())
+        }
+        Command::Research { tech: None } => {
+            let lines: Vec<String> = world
+                .techs_of(player)?
+                .into_iter()
+                .map(|(tech, status)| {
+                    let status = match status {
+                        TechStatus::Researched => String::from("researched"),
+                        TechStatus::Researching(progress) => {
+                            format!("researching, {}/{}", progress, tech.cost)
+                        }
+                        TechStatus::Available => format!("available, {} research", tech.cost),
+                        TechStatus::Locked => {
+                            format!("locked, needs {}", tech.prerequisites.join(", "))
+                        }
+                    };
+                    format!("{} ({}) - {}", tech.name, tech.id, status)
+                })
+                .collect();
+            if lines.is_empty() {
+                Ok(String::from("There is nothing to research").into())
+            } else {
+                Ok(lines.join("\n").into())
+            }
+        }
+        Command::Research { tech: Some(tech) } => {
+            world.start_research(player, &tech)?;
+            Ok(format!("Started researching {}", tech).into())
+        }
+        Command::Refit {
+            planet,
+            index,
+            blueprint,
+        } => {
+            world.refit_building(player, &planet, index, &blueprint)?;
+            Ok(format!("Building #{} on {} now builds {}", index, planet, blueprint).into())
+        }
+        Command::TradeRoutes => {
+            world.get_player(player)?;
+            let mut lines: Vec<String> = world
+                .trade_routes_of(player)
+                .map(|route| {
+                    format!(
+                        "#{} {} -> {}: {} every {} ticks",
+                        route.id,
+                        route.origin,
+                        route.destination,
+                        render_cargo(&route.cargo, &world.resources),
+                        route.interval
+                    )
+                })
+                .collect();
+            lines.extend(world.convoys_of(player).map(|convoy| {
+                format!(
+                    "Convoy {} -> {}: {}, {} ticks left",
+                    convoy.origin,
+                    convoy.destination,
+                    render_cargo(&convoy.cargo, &world.resources),
+                    convoy.ticks_remaining()
+                )
+            }));
+            if lines.is_empty() {
+                Ok(String::from("You have no trade routes").into())
+            } else {
+                Ok(lines.join("\n").into())
+            }
+        }
+        Command::CreateTradeRoute {
+            origin,
+            destination,
+            interval,
+            cargo,
+        } => {
+            let mut resources = HashMap::new();
+            for (amount, name) in cargo {
+                let resource_type = find_resource(&world.resources, &name)?;
+                if resources.insert(resource_type, amount).is_some() {
+                    return Err(GameError::InvalidTradeRoute(format!(
+                        "{} appears twice in the cargo",
+                        resource_type
+                    )));
+                }
+            }
+            let id =
+                world.create_trade_route(player, &origin, &destination, resources, interval)?;
+            Ok(format!(
+                "Trade route #{} ships from {} to {} every {} ticks",
+                id, origin, destination, interval
+            )
+            .into())
+        }
+        Command::CancelTradeRoute { id } => {
+            world.cancel_trade_route(player, id)?;
+            Ok(format!("Trade route #{} has been cancelled", id).into())
+        }
+        Command::Orders => {
+            world.get_player(player)?;
+            let lines: Vec<String> = world
+                .orders_of(player)
+                .map(|order| {
+                    format!(
+                        "#{} {} {} {} for {} Credits each from {}",
+                        order.id,
+                        order.side,
+                        order.quantity,
+                        world.resources.label(order.resource_type),
+                        order.price,
+                        order.planet
+                    )
+                })
+                .collect();
+            if lines.is_empty() {
+                Ok(String::from("You have no market orders").into())
+            } else {
+                Ok(lines.join("\n").into())
+            }
+        }
+        Command::MarketPrices { resource } => {
+            let resource_type = find_resource(&world.resources, &resource)?;
+            let (buys, sells) = world.market.order_book(resource_type);
+            let mut lines = vec![format!(
+                "Market for {}",
+                world.resources.label(resource_type)
+            )];
+            let best = |orders: &[&Order]| {
+                orders
+                    .first()
+                    .map(|order| format!("{} Credits", order.price))
+                    .unwrap_or_else(|| String::from("none"))
+            };
+            lines.push(format!(
+                "Best buy: {} ({} orders), best sell: {} ({} orders)",
+                best(&buys),
+                buys.len(),
+                best(&sells),
+                sells.len()
+            ));
+            lines.extend(render_price_history(
+                world
+                    .market
+                    .price_history(resource_type, PRICE_CHART_POINTS),
+            ));
+            Ok(lines.join("\n").into())
+        }
+        Command::PlaceOrder {
+            side,
+            planet,
+            quantity,
+            resource,
+            price,
+        } => {
+            let resource_type = find_resource(&world.resources, &resource)?;
+            let id = world.place_order(player, &planet, side, resource_type, quantity, price)?;
+            Ok(format!(
+                "Order #{}: {} {} {} for {} Credits each",
+                id,
+                side,
+                quantity,
+                world.resources.label(resource_type),
+                price
+            )
+            .into())
+        }
+        Command::CancelOrder { id } => {
+            world.cancel_order(player, id)?;
+            Ok(format!("Order #{} has been cancelled", id).into())
+        }
+        Command::Diplomacy => {
+            world.get_player(player)?;
+            let mut others: Vec<_> = world
+                .players
+                .values()
+                .filter(|other| other.id != player)
+                .collect();
+            others.sort_by(|a, b| a.name.cmp(&b.name));
+            let mut lines: Vec<String> = others
+                .iter()
+                .map(|other| {
+                    let relation = match world.diplomacy.relation(player, other.id) {
+                        Relation::Neutral => String::from("neutral"),
+                        Relation::War => String::from("at war"),
+                        Relation::Treaty(_) => {
+                            render_treaty(world.diplomacy.treaty(player, other.id))
+                        }
+                    };
+                    format!("{}: {}", other.name, relation)
+                })
+                .collect();
+            lines.extend(world.proposals_of(player).map(|proposal| {
+                let duration = match proposal.duration {
+                    Some(ticks) => format!(" for {} ticks", ticks),
+                    None => String::new(),
+                };
+                format!(
+                    "Proposal #{}: {} offers {} a {}{}",
+                    proposal.id,
+                    player_name(world, proposal.from),
+                    player_name(world, proposal.to),
+                    proposal.kind,
+                    duration
+                )
+            }));
+            if lines.is_empty() {
+                Ok(String::from("There is nobody else in the game").into())
+            } else {
+                Ok(lines.join("\n").into())
+            }
+        }
+        Command::Propose {
+            kind,
+            player: target,
+            duration,
+        } => {
+            let target = world.find_player(&target)?;
+            let id = world.propose_treaty(player, target, kind, duration)?;
+            Ok(format!(
+                "{} offers {} a {}, /accept {} to sign it",
+                message.author_name,
+                player_name(world, target),
+                kind,
+                id
+            )
+            .into())
+        }
+        Command::Accept { id } => {
+            let treaty = world.accept_proposal(player, id)?;
+            Ok(format!(
+                "{} and {} signed a {}",
+                player_name(world, treaty.parties.0),
+                player_name(world, treaty.parties.1),
+                render_treaty(Some(&treaty))
+            )
+            .into())
+        }
+        Command::Reject { id } => {
+            world.reject_proposal(player, id)?;
+            Ok(format!("Proposal #{} is off the table", id).into())
+        }
+        Command::DeclareWar { player: target } => {
+            let target = world.find_player(&target)?;
+            let announcement = match world.declare_war(player, target)? {
+                Some(treaty) => format!(
+                    "{} declared war on {}, breaking their {}",
+                    message.author_name,
+                    player_name(world, target),
+                    treaty.kind
+                ),
+                None => format!(
+                    "{} declared war on {}",
+                    message.author_name,
+                    player_name(world, target)
+                ),
+            };
+            Ok(announcement.into())
+        }
+        Command::Tick => {
+            if world.admin != Some(player) {
+                return Err(GameError::NotGameAdmin);
+            }
+            world.tick();
+            // the other players' events wait until they collect them
+            Ok(render_events("Tick done", &world.take_events(player)))
+        }
+        Command::Events => {
+            world.get_player(player)?;
+            let events = world.take_events(player);
+            if events.is_empty() {
+                return Ok(String::from("No new events").into());
+            }
+            Ok(render_events("Since you last looked:", &events))
+        }
+    }
+}
+
+/// A reply listing `events` below `header`.
+fn render_events(header: &str, events: &[TickResult]) -> Reply {
+    let embeds = events.iter().flat_map(render_event_embeds).collect();
+    let lines: Vec<String> = events.iter().map(render_event).collect();
+    let content = if lines.is_empty() {
+        String::from(header)
+    } else {
+        format!("{}\n{}", header, lines.join("\n"))
+    };
+    Reply { content, embeds }
+}
+
+pub fn render_planet(planet: &Planet, registry: &ResourceRegistry) -> String {
+    let mut resources: Vec<&Resource> = planet.resources.values().collect();
+    resources.sort_by_key(|resource| {
+        (
+            registry.display_order(resource.resource_type),
+            resource.resource_type.id(),
+        )
+    });
+    let resources: Vec<String> = resources
+        .iter()
+        .map(|resource| {
+            format!(
+                "{} {}/{}",
+                registry.label(resource.resource_type),
+                resource.amount,
+                planet.storage_capacity(resource.resource_type)
+            )
+        })
+        .collect();
+
+    let mut lines = vec![
+        format!(
+            "{} ({}, {})",
+            planet.name, planet.position.x, planet.position.y
+        ),
+        format!("Resources: {}", resources.join(", ")),
+        format!(
+            "Population: {}/{}",
+            planet.population.size, planet.population.capacity
+        ),
+    ];
+
+    if !planet.traits.is_empty() {
+        let traits: Vec<String> = planet.traits.iter().map(ToString::to_string).collect();
+        lines.insert(1, format!("Traits: {}", traits.join(", ")));
+    }
+
+    if !planet.deposits.is_empty() {
+        let mut deposits: Vec<String> = planet
+            .deposits
+            .iter()
+            .map(|(resource_type, deposit)| {
+                let remaining = match deposit.remaining {
+                    Some(remaining) => format!("{} left", remaining),
+                    None => String::from("endless"),
+                };
+                match deposit.max_per_tick {
+                    Some(max_per_tick) => {
+                        format!("{} {}, {}/tick", resource_type, remaining, max_per_tick)
+                    }
+                    None => format!("{} {}", resource_type, remaining),
+                }
+            })
+            .collect();
+        deposits.sort();
+        lines.push(format!("Deposits: {}", deposits.join("; ")));
+    }
+
+    if !planet.buildings.is_empty() {
+        lines.push(String::from("Buildings:"));
+        let staffed = planet.staffed_buildings();
+        for (index, building) in planet.buildings.iter().enumerate() {
+            let data = building.get_building_data();
+            let mut details = match building {
+                Building::MilitaryBuilding(mil) if mil.stalled => format!(
+                    ", {}/{}, stalled",
+                    mil.current_progress, mil.progress_required_to_create_military
+                ),
+                Building::MilitaryBuilding(mil) => format!(
+                    ", {}/{}",
+                    mil.current_progress, mil.progress_required_to_create_military
+                ),
+                Building::ResourceBuilding(_) | Building::StorageBuilding(_) => String::new(),
+            };
+            if !staffed[index] {
+                details.push_str(&format!(", needs {} workers", data.workers));
+            }
+            lines.push(format!(
+                "#{} {} (level {}{})",
+                index, data.name, data.level, details
+            ));
+        }
+    }
+
+    if !planet.defences.is_empty() {
+        lines.push(format!(
+            "Defences: {} turrets (defence {})",
+            planet.defences.len(),
+            planet.defence_strength()
+        ));
+    }
+
+    if !planet.construction_queue.is_empty() {
+        lines.push(String::from("Construction queue:"));
+        for (index, construction) in planet.construction_queue.status().iter().enumerate() {
+            lines.push(format!("{}. {}", index + 1, construction));
+        }
+    }
+
+    lines.join("\n")
+}
+
+fn player_name(world: &GameWorld, player: PlayerId) -> String {
+    match world.players.get(&player) {
+        Some(player) => player.name.clone(),
+        None => player.to_string(),
+    }
+}
+
+fn render_treaty(treaty: Option<&Treaty>) -> String {
+    match treaty {
+        Some(Treaty {
+            kind,
+            ticks_remaining: Some(ticks),
+            ..
+        }) => format!("{}, {} ticks left", kind, ticks),
+        Some(treaty) => treaty.kind.to_string(),
+        None => String::from("no treaty"),
+    }
+}
+
+fn find_resource(registry: &ResourceRegistry, name: &str) -> Result<ResourceType, GameError> {
+    registry
+        .find(name)
+        .ok_or_else(|| GameError::UnknownResourceName(name.to_string()))
+}
+
+/// One bar per price point, scaled to the highest price shown.
+pub fn render_price_history(history: &[PricePoint]) -> Vec<String> {
+    let highest = match history.iter().map(|point| point.price).max() {
+        Some(highest) => highest.max(1),
+        None => return vec![String::from("No trades yet")],
+    };
+    history
+        .iter()
+        .map(|point| {
+            let width = (point.price * PRICE_CHART_WIDTH / highest).max(1) as usize;
+            format!(
+                "tick {:>4} {:<width$} {} Credits, {} traded",
+                point.tick,
+                "█".repeat(width),
+                point.price,
+                point.volume,
+                width = PRICE_CHART_WIDTH as usize
+            )
+        })
+        .collect()
+}
+
+fn render_cargo(cargo: &HashMap<ResourceType, i32>, registry: &ResourceRegistry) -> String {
+    let mut cargo: Vec<(&ResourceType, &i32)> = cargo.iter().collect();
+    cargo.sort_by_key(|(resource_type, _)| {
+        (registry.display_order(**resource_type), resource_type.id())
+    });
+    cargo
+        .iter()
+        .map(|(resource_type, amount)| format!("{} {}", amount, registry.label(**resource_type)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub fn render_event(event: &TickResult) -> String {
+    match event {
+        TickResult::ResourceResult(_) | TickResult::MilitaryBuildResult(_) => {
+            format!("{:?}", event)
+        }
+        TickResult::MilitaryStalled(building, _) => {
+            format!("{} is idle, it can't pay its upkeep", building)
+        }
+        TickResult::Error(err) => err.to_string(),
+        TickResult::Notification(message) => message.clone(),
+        TickResult::Siege(report) if report.can_capture => format!(
+            "{} broke through the defences of {}",
+            report.attacker, report.planet
+        ),
+        TickResult::Siege(report) => {
+            format!("{} held against {}", report.planet, report.attacker)
+        }
+        TickResult::Captured(report) => {
+            let mut loot: Vec<String> = report
+                .loot
+                .iter()
+                .map(|(resource_type, amount)| format!("{} {}", amount, resource_type))
+                .collect();
+            loot.sort();
+            let mut message = format!("{} has been captured", report.planet);
+            if !loot.is_empty() {
+                message.push_str(&format!(", looted {}", loot.join(", ")));
+            }
+            if report.constructions_cancelled > 0 {
+                message.push_str(&format!(
+                    ", {} constructions were cancelled",
+                    report.constructions_cancelled
+                ));
+            }
+            message
+        }
+    }
+}
+
+/// Battle embeds for events that come with a battle report.
+pub fn render_event_embeds(event: &TickResult) -> Vec<Embed> {
+    match event {
+        TickResult::Siege(report) => report.battles.iter().map(render_battle_report).collect(),
+        _ => vec![],
+    }
+}
+
+pub fn render_battle_report(report: &BattleReport) -> Embed {
+    let description = match report.outcome {
+        BattleOutcome::AttackerWon => format!("{} destroyed {}", report.attacker, report.defender),
+        BattleOutcome::DefenderWon => format!("{} held off {}", report.defender, report.attacker),
+        BattleOutcome::Draw => String::from("Neither side could win the battle"),
+    };
+
+    let render_losses = |losses: &BTreeMap<String, u32>| {
+        if losses.is_empty() {
+            String::from("None")
+        } else {
+            losses
+                .iter()
+                .map(|(name, count)| format!("{}x {}", count, name))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+    };
+
+    let mut embed = Embed {
+        title: format!("Battle: {} vs {}", report.attacker, report.defender),
+        description,
+        fields: vec![
+            EmbedField {
+                name: format!("{} losses", report.attacker),
+                value: render_losses(&report.attacker_losses),
+                inline: true,
+            },
+            EmbedField {
+                name: format!("{} losses", report.defender),
+                value: render_losses(&report.defender_losses),
+                inline: true,
+            },
+            EmbedField {
+                name: String::from("Rounds"),
+                value: report.rounds.len().to_string(),
+                inline: true,
+            },
+        ],
+    };
+
+    let promotions: Vec<String> = [
+        (&report.attacker, &report.attacker_promotions),
+        (&report.defender, &report.defender_promotions),
+    ]
+    .iter()
+    .filter(|(_, promotions)| !promotions.is_empty())
+    .map(|(side, promotions)| format!("{}: {}", side, render_losses(promotions)))
+    .collect();
+    if !promotions.is_empty() {
+        embed.fields.push(EmbedField {
+            name: String::from("Promotions"),
+            value: promotions.join("\n"),
+            inline: false,
+        });
+    }
+    embed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::commands::{
+        command::{Command, ParseError},
+        gateway::{EmbedField, MockGateway, OutgoingMessage},
+        handler::{render_battle_report, render_price_history, run_pending},
+    };
+    use crate::game::{
+        catalog::BuildingCatalog,
+        combat::resolve_battle,
+        diplomacy::TreatyKind,
+        game_world::GameWorld,
+        market::{OrderSide, PricePoint},
+        military::{Fleet, MilitaryCreation, MilitaryCreationTypes},
+        planet::Planet,
+        resource::ResourceType,
+    };
+
+    const CHANNEL: u64 = 7;
+    const ALICE: u64 = 111;
+    const BOB: u64 = 222;
+
+    fn create_world() -> GameWorld {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 10, 10),
+        ]);
+        world.catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        world
+    }
+
+    fn replies(world: &mut GameWorld, gateway: &mut MockGateway) -> Vec<String> {
+        run_pending(world, gateway);
+        gateway
+            .take_sent()
+            .into_iter()
+            .map(|message| {
+                assert_eq!(message.channel, CHANNEL);
+                message.content
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            Command::parse("/build planet_1 mineral_mine"),
+            Ok(Command::Build {
+                planet: String::from("planet_1"),
+                building: String::from("mineral_mine")
+            })
+        );
+        assert_eq!(
+            Command::parse("  /FLEET planet_1 create Home Guard "),
+            Ok(Command::CreateFleet {
+                planet: String::from("planet_1"),
+                name: String::from("Home Guard")
+            })
+        );
+        assert_eq!(
+            Command::parse("/fleet split #4 2 Corvette Strike Group"),
+            Ok(Command::SplitFleet {
+                fleet: 4,
+                count: 2,
+                unit: String::from("Corvette"),
+                name: String::from("Strike Group")
+            })
+        );
+        assert_eq!(
+            Command::parse("/fleet merge 4 first"),
+            Err(ParseError::InvalidArgument(
+                "target fleet #",
+                String::from("first")
+            ))
+        );
+        assert_eq!(
+            Command::parse("/rally planet_1 2"),
+            Ok(Command::Rally {
+                planet: String::from("planet_1"),
+                index: 2,
+                fleet: None
+            })
+        );
+        assert_eq!(
+            Command::parse("/status"),
+            Ok(Command::Status { planet: None })
+        );
+        assert_eq!(
+            Command::parse("/upgrade planet_1 first"),
+            Err(ParseError::InvalidArgument(
+                "building #",
+                String::from("first")
+            ))
+        );
+        assert_eq!(
+            Command::parse("/build planet_1"),
+            Err(ParseError::MissingArgument("building"))
+        );
+        assert_eq!(
+            Command::parse("/attack"),
+            Err(ParseError::UnknownCommand(String::from("attack")))
+        );
+        assert_eq!(Command::parse("hello"), Err(ParseError::NotACommand));
+        assert_eq!(
+            Command::parse("/newgame 8 42"),
+            Ok(Command::NewGame {
+                planets: 8,
+                seed: Some(42)
+            })
+        );
+        assert_eq!(
+            Command::parse("/newgame 0"),
+            Err(ParseError::InvalidArgument("planets", String::from("0")))
+        );
+        assert_eq!(Command::parse("/join"), Ok(Command::Join { planet: None }));
+        assert_eq!(
+            Command::parse("/refit planet_1 0 frigate"),
+            Ok(Command::Refit {
+                planet: String::from("planet_1"),
+                index: 0,
+                blueprint: String::from("frigate")
+            })
+        );
+        assert_eq!(
+            Command::parse("/trade planet_1 planet_2 3 10 credits 5 food"),
+            Ok(Command::CreateTradeRoute {
+                origin: String::from("planet_1"),
+                destination: String::from("planet_2"),
+                interval: 3,
+                cargo: vec![(10, String::from("credits")), (5, String::from("food"))]
+            })
+        );
+        assert_eq!(
+            Command::parse("/sell planet_1 10 minerals 5"),
+            Ok(Command::PlaceOrder {
+                side: OrderSide::Sell,
+                planet: String::from("planet_1"),
+                quantity: 10,
+                resource: String::from("minerals"),
+                price: 5
+            })
+        );
+        assert_eq!(
+            Command::parse("/buy planet_1 10 minerals cheap"),
+            Err(ParseError::InvalidArgument("price", String::from("cheap")))
+        );
+        assert_eq!(
+            Command::parse("/market cancel 4"),
+            Ok(Command::CancelOrder { id: 4 })
+        );
+        assert_eq!(
+            Command::parse("/propose pact bob 10"),
+            Ok(Command::Propose {
+                kind: TreatyKind::NonAggression,
+                player: String::from("bob"),
+                duration: Some(10)
+            })
+        );
+        assert_eq!(
+            Command::parse("/propose friendship bob"),
+            Err(ParseError::InvalidArgument(
+                "alliance|pact",
+                String::from("friendship")
+            ))
+        );
+        assert_eq!(Command::parse("/accept #3"), Ok(Command::Accept { id: 3 }));
+        assert_eq!(
+            Command::parse("/trade cancel #2"),
+            Ok(Command::CancelTradeRoute { id: 2 })
+        );
+        assert_eq!(
+            Command::parse("/trade planet_1 planet_2 3 10"),
+            Err(ParseError::MissingArgument("resource"))
+        );
+    }
+
+    #[test]
+    fn price_history_chart() {
+        assert_eq!(
+            render_price_history(&[]),
+            vec![String::from("No trades yet")]
+        );
+        assert_eq!(
+            render_price_history(&[
+                PricePoint {
+                    tick: 3,
+                    price: 10,
+                    volume: 4
+                },
+                PricePoint {
+                    tick: 12,
+                    price: 5,
+                    volume: 20
+                },
+            ]),
+            vec![
+                format!("tick    3 {} 10 Credits, 4 traded", "█".repeat(20)),
+                format!(
+                    "tick   12 {}{} 5 Credits, 20 traded",
+                    "█".repeat(10),
+                    " ".repeat(10)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn war_is_announced_through_gateway() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/join planet_2");
+        gateway.push(CHANNEL, ALICE, "alice", "/propose pact Bob");
+        gateway.push(CHANNEL, BOB, "bob", "/accept 1");
+        gateway.push(CHANNEL, ALICE, "alice", "/diplomacy");
+        gateway.push(CHANNEL, ALICE, "alice", "/war bob");
+        gateway.push(CHANNEL, BOB, "bob", "/diplomacy");
+
+        assert_eq!(
+            replies(&mut world, &mut gateway)[2..],
+            [
+                "alice offers bob a non-aggression pact, /accept 1 to sign it",
+                "alice and bob signed a non-aggression pact",
+                "bob: non-aggression pact",
+                "alice declared war on bob, breaking their non-aggression pact",
+                "alice: at war",
+            ]
+        );
+    }
+
+    #[test]
+    fn new_game_assigns_home_planets() {
+        let mut world = GameWorld::new(vec![]);
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/newgame 4");
+        gateway.push(CHANNEL, ALICE, "alice", "/join");
+        gateway.push(CHANNEL, BOB, "bob", "/newgame 4");
+        gateway.push(CHANNEL, BOB, "bob", "/join");
+
+        let replies = replies(&mut world, &mut gateway);
+        assert_eq!(
+            replies[0],
+            "Created a galaxy of 4 planets (seed 7), /join to get a home planet"
+        );
+        assert_eq!(replies[2], "The game has already started");
+        assert_eq!(world.planets.len(), 4);
+
+        let alice_home = world.planets_of(ALICE).next().unwrap();
+        let bob_home = world.planets_of(BOB).next().unwrap();
+        assert_eq!(
+            replies[1],
+            format!("Welcome alice, your home planet is {}", alice_home.name)
+        );
+        assert_eq!(
+            replies[3],
+            format!("Welcome bob, your home planet is {}", bob_home.name)
+        );
+        assert_ne!(alice_home.name, bob_home.name);
+    }
+
+    #[test]
+    fn only_the_admin_ticks_and_sees_their_own_events() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/join planet_2");
+        replies(&mut world, &mut gateway);
+        for planet in world.planets.iter_mut() {
+            if let Some(credits) = planet.resources.get_mut(&ResourceType::Credits) {
+                credits.amount = 100;
+            }
+        }
+        for (player, name, planet) in [(ALICE, "alice", "planet_1"), (BOB, "bob", "planet_2")] {
+            gateway.push(
+                CHANNEL,
+                player,
+                name,
+                &format!("/build {} colony_hub", planet),
+            );
+            gateway.push(
+                CHANNEL,
+                player,
+                name,
+                &format!("/build {} mineral_mine", planet),
+            );
+        }
+        replies(&mut world, &mut gateway);
+
+        gateway.push(CHANNEL, BOB, "bob", "/tick");
+        gateway.push(CHANNEL, 333, "carol", "/tick");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Only the player running the game can advance it",
+                "Only the player running the game can advance it",
+            ]
+        );
+
+        // both mines are finished, alice only hears about hers
+        for _ in 0..3 {
+            gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        }
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Tick done",
+                "Tick done",
+                "Tick done\nMineral Mine has been built on planet_1",
+            ]
+        );
+        assert_eq!(world.planets[1].buildings.len(), 2);
+
+        // bob's events waited for him, and he collects them only once
+        gateway.push(CHANNEL, BOB, "bob", "/events");
+        gateway.push(CHANNEL, BOB, "bob", "/events");
+        gateway.push(CHANNEL, 333, "carol", "/events");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Since you last looked:\nMineral Mine has been built on planet_2",
+                "No new events",
+                "You haven't joined the game yet",
+            ]
+        );
+    }
+
+    #[test]
+    fn join_build_and_tick_through_gateway() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/build planet_1 colony_hub");
+        gateway.push(CHANNEL, ALICE, "alice", "/build planet_1 colony_hub");
+        gateway.push(CHANNEL, ALICE, "alice", "/build planet_1 shipyard");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Welcome alice, planet_1 is now yours",
+                "planet_1 already has an owner",
+                "You haven't joined the game yet",
+                "Started building colony_hub on planet_1",
+                "You need to build Mineral Mine first",
+            ]
+        );
+
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        gateway.push(CHANNEL, ALICE, "alice", "/status planet_1");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Tick done",
+                "planet_1 (0, 0)\nResources: 💰 Credits 5/1000, 💎 Minerals 0/1000, 🌾 Food 2/1000, 🔬 Research 0/1000\nPopulation: 1/50\nBuildings:\n#0 Colony Hub (level 1)",
+            ]
+        );
+
+        if let Some(credits) = world.planets[0].resources.get_mut(&ResourceType::Credits) {
+            credits.amount = 20;
+        }
+        gateway.push(CHANNEL, ALICE, "alice", "/build planet_1 mineral_mine");
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        gateway.push(CHANNEL, ALICE, "alice", "/status planet_1");
+        gateway.push(CHANNEL, ALICE, "alice", "/status");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Started building mineral_mine on planet_1",
+                "Tick done",
+                "planet_1 (0, 0)\nResources: 💰 Credits 5/1000, 💎 Minerals 0/1000, 🌾 Food 3/1000, 🔬 Research 0/1000\nPopulation: 2/50\nBuildings:\n#0 Colony Hub (level 1)\nConstruction queue:\n1. Mineral Mine (1/3 ticks)",
+                "planet_1 (0, 0) - 1 buildings, 1 in construction",
+            ]
+        );
+
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Tick done",
+                "Tick done\nMineral Mine has been built on planet_1"
+            ]
+        );
+    }
+
+    #[test]
+    fn trade_cargo_names_each_resource_once() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(
+            CHANNEL,
+            ALICE,
+            "alice",
+            "/trade planet_1 planet_2 3 2147483647 credits 1 credits",
+        );
+        gateway.push(
+            CHANNEL,
+            ALICE,
+            "alice",
+            "/trade planet_1 planet_2 3 10 credits",
+        );
+        assert_eq!(
+            replies(&mut world, &mut gateway),
+            vec![
+                "Welcome alice, planet_1 is now yours",
+                "Invalid trade route: Credits appears twice in the cargo",
+                "Trade route #1 ships from planet_1 to planet_2 every 3 ticks",
+            ]
+        );
+    }
+
+    #[test]
+    fn fleet_commands_through_gateway() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/join planet_2");
+        gateway.push(CHANNEL, ALICE, "alice", "/fleet planet_1 create Home Guard");
+        gateway.push(CHANNEL, BOB, "bob", "/fleet planet_1 create Raiders");
+        gateway.push(CHANNEL, ALICE, "alice", "/fleet planet_1");
+        gateway.push(CHANNEL, ALICE, "alice", "/upgrade planet_1 0");
+        gateway.push(CHANNEL, ALICE, "alice", "/dance");
+
+        let sent = {
+            run_pending(&mut world, &mut gateway);
+            gateway.take_sent()
+        };
+        assert_eq!(
+            sent.last(),
+            Some(&OutgoingMessage {
+                channel: CHANNEL,
+                content: String::from("Unknown command /dance, try /help"),
+                embeds: vec![],
+            })
+        );
+        let sent: Vec<&str> = sent
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(
+            sent[..6],
+            [
+                "Welcome alice, planet_1 is now yours",
+                "Welcome bob, planet_2 is now yours",
+                "Created fleet #3 Home Guard on planet_1",
+                "You don't own planet_1",
+                "#1 planet_1 fleet: no units\n#3 Home Guard: no units",
+                "There is no building #0",
+            ]
+        );
+    }
+
+    #[test]
+    fn battle_report_embed() {
+        let mut attacker = Fleet::new(None);
+        let mut defender = Fleet::new(None);
+        for _ in 0..2 {
+            attacker
+                .ships
+                .push(MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                    String::from("Corvette"),
+                    1,
+                    5,
+                    5,
+                )));
+        }
+        defender
+            .ships
+            .push(MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                String::from("Drone"),
+                1,
+                1,
+                3,
+            )));
+
+        let report = resolve_battle("Raiders", &mut attacker, "Home Guard", &mut defender);
+        let embed = render_battle_report(&report);
+        assert_eq!(embed.title, "Battle: Raiders vs Home Guard");
+        assert_eq!(embed.description, "Raiders destroyed Home Guard");
+        assert_eq!(
+            embed.fields[..2],
+            [
+                EmbedField {
+                    name: String::from("Raiders losses"),
+                    value: String::from("None"),
+                    inline: true,
+                },
+                EmbedField {
+                    name: String::from("Home Guard losses"),
+                    value: String::from("1x Drone"),
+                    inline: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn move_fleet_through_gateway() {
+        let mut world = create_world();
+        let mut gateway = MockGateway::default();
+
+        gateway.push(CHANNEL, ALICE, "alice", "/join planet_1");
+        gateway.push(CHANNEL, BOB, "bob", "/join planet_2");
+        run_pending(&mut world, &mut gateway);
+        gateway.take_sent();
+
+        world.planets[0]
+            .add_military_unit(MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                String::from("Corvette"),
+                1,
+                5,
+                5,
+            )))
+            .unwrap();
+        world.planets[1].defences =
+            vec![MilitaryCreation::new(String::from("Flak Turret"), 1, 1, 2)];
+
+        gateway.push(
+            CHANNEL,
+            ALICE,
+            "alice",
+            "/move planet_1 planet_2 planet_1 fleet",
+        );
+        gateway.push(CHANNEL, ALICE, "alice", "/status");
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        gateway.push(CHANNEL, ALICE, "alice", "/tick");
+        run_pending(&mut world, &mut gateway);
+        let sent = gateway.take_sent();
+
+        let contents: Vec<&str> = sent
+            .iter()
+            .map(|message| message.content.as_str())
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                "planet_1 fleet will arrive at planet_2 in 3 ticks",
+                "planet_1 (0, 0) - 0 buildings, 0 in construction\nplanet_1 fleet: planet_1 -> planet_2, 3 ticks left",
+                "Tick done",
+                "Tick done",
+                "Tick done\nplanet_1 fleet broke through the defences of planet_2\nplanet_2 has been captured\nplanet_1 fleet arrived at planet_2",
+            ]
+        );
+        assert!(sent[..4].iter().all(|message| message.embeds.is_empty()));
+        assert_eq!(sent[4].embeds.len(), 1);
+        assert_eq!(
+            sent[4].embeds[0].title,
+            "Battle: planet_1 fleet vs planet_2 defences"
+        );
+    }
+}
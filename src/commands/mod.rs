@@ -0,0 +1,3 @@
+pub mod command;
+pub mod gateway;
+pub mod handler;
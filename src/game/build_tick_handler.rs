@@ -1,31 +1,149 @@
 use mediator_sys::synchronous::basic::*;
 
+use std::collections::HashMap;
+
 use super::{
+    buildings::Building,
+    error::GameError,
     planet::Planet,
-    tickable::{TickResult, Tickable},
+    resource::ResourceType,
+    tickable::{TickEvent, TickResult, Tickable},
 };
 
 pub struct BuildingTickRequest<'a> {
     pub planet: &'a mut Planet,
+    /// production bonuses the owner researched
+    pub bonuses: HashMap<ResourceType, i32>,
 }
 
-impl RequestHandler<BuildingTickRequest<'_>, TickResult> for BasicMediator<TickResult> {
+impl RequestHandler<BuildingTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
     fn handle(&self, req: BuildingTickRequest) {
-        let buildings = req.planet.buildings.iter_mut();
-        for building in buildings {
-            let tick_result = building.tick();
+        req.planet.ledger.advance_tick();
+        for deposit in req.planet.deposits.values_mut() {
+            deposit.extracted_this_tick = 0;
+        }
+
+        let staffed = req.planet.staffed_buildings();
+        let mut wasted: HashMap<ResourceType, i32> = HashMap::new();
+        for (index, staffed) in staffed.into_iter().enumerate() {
+            if !staffed {
+                continue;
+            }
+            // the building is taken out of the planet so it can pay from it while
+            // ticking, the others stay so storage buildings keep counting
+            let mut building = req.planet.buildings.remove(index);
+            tick_building(self, req.planet, &mut building, &req.bonuses, &mut wasted);
+            req.planet.buildings.insert(index, building);
+        }
+
+        if !wasted.is_empty() {
+            let mut lost: Vec<String> = wasted
+                .iter()
+                .map(|(resource_type, amount)| format!("{} {}", amount, resource_type))
+                .collect();
+            lost.sort();
+            self.publish(TickEvent::new(
+                [req.planet.owner],
+                TickResult::Notification(format!(
+                    "Storage on {} is full, {} went to waste",
+                    req.planet.name,
+                    lost.join(", ")
+                )),
+            ));
+        }
+    }
+}
 
-            match tick_result {
-                TickResult::ResourceResult(res) => {
-                    Planet::add_resource(&mut req.planet.resources, res)
+fn tick_building(
+    mediator: &BasicMediator<TickEvent>,
+    planet: &mut Planet,
+    building: &mut Building,
+    bonuses: &HashMap<ResourceType, i32>,
+    wasted: &mut HashMap<ResourceType, i32>,
+) {
+    let building_name = building.get_building_data().name.clone();
+    // the owner may have several planets with the same building
+    let located_name = format!("{} on {}", building_name, planet.name);
+    let owner = planet.owner;
+    let publish = |result: TickResult| mediator.publish(TickEvent::new([owner], result));
+    let rally_fleet = match building {
+        Building::MilitaryBuilding(mil) => mil.rally_fleet,
+        _ => None,
+    };
+
+    let mut effects = vec![];
+    if let Building::MilitaryBuilding(mil) = building {
+        // only the costs are paid up front, whatever the building yields is
+        // produced like any other output so storage, bonuses and deposits apply
+        let (yields, costs): (HashMap<_, _>, HashMap<_, _>) = mil
+            .military_cost_per_tick
+            .iter()
+            .map(|(resource_type, resource)| (*resource_type, *resource))
+            .partition(|(_, resource)| resource.amount > 0);
+        match planet.apply_transaction(&building_name, &costs) {
+            Ok(()) => mil.stalled = false,
+            Err(GameError::InsufficientResources(shortfall)) => {
+                mil.stalled = true;
+                publish(TickResult::MilitaryStalled(located_name, shortfall));
+                return;
+            }
+            Err(err) => {
+                mil.stalled = true;
+                publish(TickResult::Error(err));
+                return;
+            }
+        }
+        if !yields.is_empty() {
+            effects.push(TickResult::ResourceResult(yields));
+        }
+    }
+    effects.extend(building.tick());
+
+    // effects are applied in order, once a resource effect can't be paid
+    // the rest of that building's effects for this tick are dropped
+    for effect in effects {
+        match effect {
+            TickResult::ResourceResult(res) => {
+                match planet.produce(&building_name, &res, bonuses) {
+                    Ok(production) => {
+                        for (resource_type, amount) in production.wasted {
+                            *wasted.entry(resource_type).or_insert(0) += amount;
+                        }
+                        for resource_type in production.depleted {
+                            publish(TickResult::Notification(format!(
+                                "The {} deposit on {} is depleted",
+                                resource_type, planet.name
+                            )));
+                        }
+                    }
+                    Err(GameError::InsufficientResources(shortfall)) => {
+                        publish(TickResult::Error(GameError::UpkeepUnpaid(
+                            located_name,
+                            shortfall,
+                        )));
+                        break;
+                    }
+                    Err(err) => {
+                        publish(TickResult::Error(err));
+                        break;
+                    }
                 }
-                TickResult::MilitaryBuildResult(mil) => {
-                    let planet_name = req.planet.name.clone();
-                    let fleet_name = format!("{}{}", planet_name, String::from(" fleet"));
-                    Planet::add_military(req.planet.military.get_mut(&fleet_name).unwrap(), mil)
+            }
+            TickResult::MilitaryBuildResult(mil) => {
+                let unit_name = mil.get_creation().name.clone();
+                match planet.add_military_unit_to(mil, rally_fleet) {
+                    Ok(()) => publish(TickResult::Notification(format!(
+                        "{} finished building {}",
+                        located_name, unit_name
+                    ))),
+                    Err(err) => publish(TickResult::Error(err)),
                 }
-                TickResult::None => (),
             }
+            event @ (TickResult::MilitaryStalled(..)
+            | TickResult::Error(_)
+            | TickResult::Notification(_)
+            | TickResult::Siege(_)
+            | TickResult::Captured(_)) => publish(event),
         }
     }
 }
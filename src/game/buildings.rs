@@ -1,21 +1,29 @@
 use std::collections::HashMap;
 
+use serde::{Deserialize, Serialize};
+
 use super::{
-    military::MilitaryCreationTypes,
+    error::GameError,
+    military::{FleetId, MilitaryCreationTypes},
     resource::{Resource, ResourceType},
     tickable::{TickResult, Tickable},
 };
 
+pub const MAX_BUILDING_LEVEL: u32 = 10;
+
+#[derive(Serialize, Deserialize)]
 pub enum Building {
     MilitaryBuilding(MilitaryBuilding),
     ResourceBuilding(ResourceBuilding),
+    StorageBuilding(StorageBuilding),
 }
 
 impl Tickable for Building {
-    fn tick(&mut self) -> TickResult {
+    fn tick(&mut self) -> Vec<TickResult> {
         match self {
             Self::MilitaryBuilding(mil) => mil.tick(),
             Self::ResourceBuilding(res) => res.tick(),
+            Self::StorageBuilding(_) => vec![],
         }
     }
 }
@@ -25,17 +33,97 @@ impl Building {
         match self {
             Self::MilitaryBuilding(mil) => &mil.building,
             Self::ResourceBuilding(res) => &res.building,
+            Self::StorageBuilding(storage) => &storage.building,
+        }
+    }
+
+    pub fn get_building_data_mut(&mut self) -> &mut BuildingData {
+        match self {
+            Self::MilitaryBuilding(mil) => &mut mil.building,
+            Self::ResourceBuilding(res) => &mut res.building,
+            Self::StorageBuilding(storage) => &mut storage.building,
         }
     }
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct BuildingData {
     pub name: String,
     pub resource_cost: HashMap<ResourceType, Resource>,
     pub level: u32,
+    /// ticks spent in the construction queue, 0 builds immediately
+    pub build_time: u32,
+    /// population needed to run the building, unstaffed buildings do nothing
+    #[serde(default)]
+    pub workers: u32,
+    /// id of the catalog template the building was built from
+    #[serde(default)]
+    pub catalog_id: Option<String>,
+}
+
+impl BuildingData {
+    /// Whether the building was built from the catalog template `id`. Buildings
+    /// saved before catalog ids were kept only have their `name` to go by.
+    pub fn is_template(&self, id: &str, name: &str) -> bool {
+        match &self.catalog_id {
+            Some(catalog_id) => catalog_id == id,
+            None => self.name == name,
+        }
+    }
+
+    pub fn check_cost(&self, resources: &HashMap<ResourceType, Resource>) -> Result<(), GameError> {
+        let mut shortfall = HashMap::new();
+
+        for (resource_type, cost) in &self.resource_cost {
+            let planet_resource = match resources.get(resource_type) {
+                Some(planet_resource) => planet_resource,
+                None => return Err(GameError::UnknownResource(*resource_type)),
+            };
+
+            if planet_resource.amount < cost.amount {
+                shortfall.insert(*resource_type, cost.amount - planet_resource.amount);
+            }
+        }
+
+        if shortfall.is_empty() {
+            Ok(())
+        } else {
+            Err(GameError::InsufficientResources(shortfall))
+        }
+    }
+
+    /// Multiplies every amount in `resources` by the current level.
+    pub fn scale_by_level(
+        &self,
+        resources: &HashMap<ResourceType, Resource>,
+    ) -> HashMap<ResourceType, Resource> {
+        scale_resources(resources, self.level)
+    }
+
+    /// The cost of raising the building to the next level, `resource_cost` times that level.
+    pub fn upgrade_cost(&self) -> HashMap<ResourceType, Resource> {
+        scale_resources(&self.resource_cost, self.level + 1)
+    }
 }
 
-#[derive(Clone)]
+fn scale_resources(
+    resources: &HashMap<ResourceType, Resource>,
+    factor: u32,
+) -> HashMap<ResourceType, Resource> {
+    resources
+        .iter()
+        .map(|(resource_type, resource)| {
+            (
+                *resource_type,
+                Resource {
+                    resource_type: *resource_type,
+                    amount: resource.amount * factor as i32,
+                },
+            )
+        })
+        .collect()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 pub struct MilitaryBuilding {
     pub building: BuildingData,
     pub create_type: MilitaryCreationTypes,
@@ -43,6 +131,11 @@ pub struct MilitaryBuilding {
     pub progress_required_to_create_military: u32,
     pub current_progress: u32,
     pub progress_increase_per_tick: u32,
+    /// set while the planet can't pay `military_cost_per_tick`
+    pub stalled: bool,
+    /// the fleet new ships join, the planet's default fleet when it isn't stationed there
+    #[serde(default)]
+    pub rally_fleet: Option<FleetId>,
 }
 
 impl MilitaryBuilding {
@@ -60,34 +153,57 @@ impl MilitaryBuilding {
                 name,
                 resource_cost,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             military_cost_per_tick,
             progress_required_to_create_military,
             current_progress: 0,
             progress_increase_per_tick,
+            stalled: false,
+            rally_fleet: None,
         }
     }
 }
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct ResourceBuilding {
     pub building: BuildingData,
     pub create_type: HashMap<ResourceType, Resource>,
 }
 
+/// The costs in `military_cost_per_tick` are charged by the tick handler before
+/// ticking and its positive amounts are produced alongside the building's effects,
+/// a military building is only ticked when the planet could pay for it.
 impl Tickable for MilitaryBuilding {
-    fn tick(&mut self) -> TickResult {
-        self.current_progress += self.progress_increase_per_tick;
+    fn tick(&mut self) -> Vec<TickResult> {
+        self.current_progress += self.progress_increase_per_tick * self.building.level;
         if self.current_progress >= self.progress_required_to_create_military {
             self.current_progress = 0;
-            TickResult::MilitaryBuildResult(self.create_type.clone())
+            vec![TickResult::MilitaryBuildResult(self.create_type.clone())]
         } else {
-            TickResult::ResourceResult(self.military_cost_per_tick.clone())
+            vec![]
         }
     }
 }
 
 impl Tickable for ResourceBuilding {
-    fn tick(&mut self) -> TickResult {
-        TickResult::ResourceResult(self.create_type.clone())
+    fn tick(&mut self) -> Vec<TickResult> {
+        vec![TickResult::ResourceResult(
+            self.building.scale_by_level(&self.create_type),
+        )]
+    }
+}
+
+/// Raises how much of each resource the planet can hold, scaled by level.
+#[derive(Serialize, Deserialize)]
+pub struct StorageBuilding {
+    pub building: BuildingData,
+    pub capacity: HashMap<ResourceType, Resource>,
+}
+
+impl StorageBuilding {
+    pub fn capacity(&self) -> HashMap<ResourceType, Resource> {
+        self.building.scale_by_level(&self.capacity)
     }
 }
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::buildings::Building;
+use super::military::Fleet;
+use super::planet::Planet;
+use super::player::PlayerId;
+use super::resource::{Resource, ResourceType};
+
+/// What happens to the buildings of a captured planet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuildingFate {
+    Keep,
+    /// every building drops to half its level, military production restarts
+    Damage,
+    Destroy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureRules {
+    /// share of the planet's resources the attacker carries off
+    pub loot_percent: i32,
+    pub building_fate: BuildingFate,
+}
+
+impl Default for CaptureRules {
+    fn default() -> Self {
+        Self {
+            loot_percent: 50,
+            building_fate: BuildingFate::Damage,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureReport {
+    pub planet: String,
+    pub previous_owner: Option<PlayerId>,
+    pub new_owner: Option<PlayerId>,
+    pub loot: HashMap<ResourceType, i32>,
+    pub buildings_destroyed: usize,
+    pub buildings_damaged: usize,
+    /// constructions dropped from the queue without a refund
+    #[serde(default)]
+    pub constructions_cancelled: usize,
+}
+
+/// Hands `planet` to `new_owner`, takes the loot out of its resources and
+/// applies `rules.building_fate`. The loot is returned in the report, it is
+/// up to the caller to deliver it.
+pub fn capture_planet(
+    planet: &mut Planet,
+    new_owner: Option<PlayerId>,
+    rules: &CaptureRules,
+) -> CaptureReport {
+    let previous_owner = planet.owner;
+
+    let loot: HashMap<ResourceType, i32> = planet
+        .resources
+        .iter()
+        .filter(|(_, resource)| resource.amount > 0)
+        .map(|(resource_type, resource)| {
+            // computed in i64 so large stockpiles don't overflow, the share fits back in i32
+            let share = resource.amount as i64 * rules.loot_percent.clamp(0, 100) as i64 / 100;
+            (*resource_type, share as i32)
+        })
+        .filter(|(_, amount)| *amount > 0)
+        .collect();
+    let debit: HashMap<ResourceType, Resource> = loot
+        .iter()
+        .map(|(resource_type, amount)| {
+            (
+                *resource_type,
+                Resource {
+                    resource_type: *resource_type,
+                    amount: -amount,
+                },
+            )
+        })
+        .collect();
+    // the loot never exceeds what the planet has, so this can't fail
+    planet.apply_transaction("Looted", &debit).ok();
+
+    let mut buildings_destroyed = 0;
+    let mut buildings_damaged = 0;
+    let mut constructions_cancelled = 0;
+    match rules.building_fate {
+        BuildingFate::Keep => (),
+        BuildingFate::Damage => {
+            for building in planet.buildings.iter_mut() {
+                let data = building.get_building_data_mut();
+                data.level = (data.level / 2).max(1);
+                if let Building::MilitaryBuilding(mil) = building {
+                    mil.current_progress = 0;
+                }
+                buildings_damaged += 1;
+            }
+            constructions_cancelled = std::mem::take(&mut planet.construction_queue).len();
+        }
+        BuildingFate::Destroy => {
+            buildings_destroyed = planet.buildings.len();
+            planet.buildings.clear();
+            constructions_cancelled = std::mem::take(&mut planet.construction_queue).len();
+        }
+    }
+
+    // the previous owner's fleets were beaten, the default fleet starts over for the new owner
+    planet.owner = new_owner;
+    planet
+        .military
+        .retain(|_, fleet| fleet.owner == new_owner || !fleet.ships.is_empty());
+
+    let default_fleet = planet.default_fleet_name();
+    let previous_default = planet.military.remove(&default_fleet);
+    match previous_default {
+        Some(fleet) if fleet.owner == new_owner => {
+            planet.military.insert(default_fleet, fleet);
+        }
+        previous_default => {
+            let mut fleet = Fleet::new(new_owner);
+            fleet.position = planet.position.clone();
+            planet.military.insert(default_fleet.clone(), fleet);
+            // someone else's ships keep orbiting under another name
+            if let Some(previous_default) = previous_default {
+                planet.station_fleet(&default_fleet, previous_default);
+            }
+        }
+    }
+
+    CaptureReport {
+        planet: planet.name.clone(),
+        previous_owner,
+        new_owner,
+        loot,
+        buildings_destroyed,
+        buildings_damaged,
+        constructions_cancelled,
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::buildings::{
+    Building, BuildingData, MilitaryBuilding, ResourceBuilding, StorageBuilding,
+};
+use super::error::GameError;
+use super::military::{MilitaryCreation, MilitaryCreationTypes};
+use super::resource::{resource_map, ResourceType};
+use super::resource_registry::ResourceRegistry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MilitaryKind {
+    Ship,
+    DefenceTurret,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MilitaryBlueprint {
+    pub kind: MilitaryKind,
+    pub name: String,
+    pub attack: u32,
+    pub defence: u32,
+    #[serde(default)]
+    pub upkeep: HashMap<ResourceType, i32>,
+    pub progress_required: u32,
+    pub progress_per_tick: u32,
+}
+
+impl MilitaryBlueprint {
+    pub fn create(&self) -> MilitaryCreationTypes {
+        let creation = MilitaryCreation::new(self.name.clone(), 1, self.attack, self.defence);
+        match self.kind {
+            MilitaryKind::Ship => MilitaryCreationTypes::Ship(creation),
+            MilitaryKind::DefenceTurret => MilitaryCreationTypes::DefenceTurret(creation),
+        }
+    }
+}
+
+/// A building as described in the catalog file, it either `produces` resources,
+/// builds `military` units or adds `storage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildingTemplate {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub cost: HashMap<ResourceType, i32>,
+    #[serde(default)]
+    pub build_time: u32,
+    #[serde(default)]
+    pub workers: u32,
+    /// ids of catalog buildings that must already stand on the planet
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    pub produces: Option<HashMap<ResourceType, i32>>,
+    pub military: Option<MilitaryBlueprint>,
+    /// extra storage for each resource
+    pub storage: Option<HashMap<ResourceType, i32>>,
+}
+
+impl BuildingTemplate {
+    pub fn create(&self) -> Building {
+        let building = BuildingData {
+            name: self.name.clone(),
+            resource_cost: resource_map(&self.cost),
+            level: 1,
+            build_time: self.build_time,
+            workers: self.workers,
+            catalog_id: Some(self.id.clone()),
+        };
+
+        match (&self.produces, &self.military, &self.storage) {
+            (_, Some(military), _) => {
+                let mut military_building = MilitaryBuilding::new(
+                    self.name.clone(),
+                    building.resource_cost.clone(),
+                    military.create(),
+                    resource_map(&military.upkeep),
+                    military.progress_required,
+                    military.progress_per_tick,
+                );
+                military_building.building = building;
+                Building::MilitaryBuilding(military_building)
+            }
+            (None, None, Some(storage)) => Building::StorageBuilding(StorageBuilding {
+                building,
+                capacity: resource_map(storage),
+            }),
+            (produces, None, _) => Building::ResourceBuilding(ResourceBuilding {
+                building,
+                create_type: resource_map(&produces.clone().unwrap_or_default()),
+            }),
+        }
+    }
+}
+
+/// A unit a military building can be refitted to build instead of its own.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlueprintTemplate {
+    pub id: String,
+    /// id of the catalog building that can build it
+    pub building: String,
+    #[serde(flatten)]
+    pub unit: MilitaryBlueprint,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildingCatalog {
+    pub buildings: Vec<BuildingTemplate>,
+    #[serde(default)]
+    pub blueprints: Vec<BlueprintTemplate>,
+}
+
+impl BuildingCatalog {
+    pub fn from_toml_str(source: &str) -> Result<Self, GameError> {
+        let catalog: Self =
+            toml::from_str(source).map_err(|err| GameError::InvalidCatalog(err.to_string()))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    pub fn from_json_str(source: &str) -> Result<Self, GameError> {
+        let catalog: Self = serde_json::from_str(source)
+            .map_err(|err| GameError::InvalidCatalog(err.to_string()))?;
+        catalog.validate()?;
+        Ok(catalog)
+    }
+
+    /// Loads a `.toml` or `.json` catalog file.
+    pub fn load(path: &Path) -> Result<Self, GameError> {
+        let source =
+            fs::read_to_string(path).map_err(|err| GameError::InvalidCatalog(err.to_string()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&source),
+            Some("json") => Self::from_json_str(&source),
+            _ => Err(GameError::InvalidCatalog(format!(
+                "{} is neither a .toml nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&BuildingTemplate> {
+        self.buildings.iter().find(|template| template.id == id)
+    }
+
+    pub fn get_blueprint(&self, id: &str) -> Option<&BlueprintTemplate> {
+        self.blueprints.iter().find(|blueprint| blueprint.id == id)
+    }
+
+    /// Fails if a building costs, produces or pays upkeep in a resource type
+    /// `registry` doesn't know.
+    pub fn check_resources(&self, registry: &ResourceRegistry) -> Result<(), GameError> {
+        let resource_types = registry.resource_types();
+        for template in &self.buildings {
+            let used = template
+                .cost
+                .keys()
+                .chain(
+                    template
+                        .produces
+                        .iter()
+                        .flat_map(|produces| produces.keys()),
+                )
+                .chain(template.storage.iter().flat_map(|storage| storage.keys()))
+                .chain(
+                    template
+                        .military
+                        .iter()
+                        .flat_map(|military| military.upkeep.keys()),
+                );
+            for resource_type in used {
+                if !resource_types.contains(resource_type) {
+                    return Err(GameError::InvalidCatalog(format!(
+                        "building \"{}\" uses unknown resource \"{}\"",
+                        template.id, resource_type
+                    )));
+                }
+            }
+        }
+        for blueprint in &self.blueprints {
+            if let Some(resource_type) = blueprint
+                .unit
+                .upkeep
+                .keys()
+                .find(|resource_type| !resource_types.contains(resource_type))
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "blueprint \"{}\" uses unknown resource \"{}\"",
+                    blueprint.id, resource_type
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        for (index, template) in self.buildings.iter().enumerate() {
+            if self.buildings[..index]
+                .iter()
+                .any(|other| other.id == template.id)
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "building id \"{}\" is used more than once",
+                    template.id
+                )));
+            }
+            if template.produces.is_some() && template.military.is_some() {
+                return Err(GameError::InvalidCatalog(format!(
+                    "building \"{}\" can't both produce resources and military",
+                    template.id
+                )));
+            }
+            if template.storage.is_some()
+                && (template.produces.is_some() || template.military.is_some())
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "building \"{}\" can't both store and produce",
+                    template.id
+                )));
+            }
+            if let Some(missing) = template
+                .prerequisites
+                .iter()
+                .find(|prerequisite| self.get(prerequisite).is_none())
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "building \"{}\" requires unknown building \"{}\"",
+                    template.id, missing
+                )));
+            }
+        }
+
+        for (index, blueprint) in self.blueprints.iter().enumerate() {
+            if self.blueprints[..index]
+                .iter()
+                .any(|other| other.id == blueprint.id)
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "blueprint id \"{}\" is used more than once",
+                    blueprint.id
+                )));
+            }
+            if self
+                .get(&blueprint.building)
+                .is_none_or(|template| template.military.is_none())
+            {
+                return Err(GameError::InvalidCatalog(format!(
+                    "blueprint \"{}\" needs a military building, \"{}\" isn't one",
+                    blueprint.id, blueprint.building
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
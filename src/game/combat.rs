@@ -0,0 +1,145 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::military::{Fleet, MilitaryCreationTypes, EXPERIENCE_PER_BATTLE, EXPERIENCE_PER_KILL};
+
+pub const MAX_BATTLE_ROUNDS: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BattleOutcome {
+    AttackerWon,
+    DefenderWon,
+    Draw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleRound {
+    pub attacker_damage: u32,
+    pub defender_damage: u32,
+    /// names of the units destroyed this round
+    pub attacker_losses: Vec<String>,
+    pub defender_losses: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BattleReport {
+    pub attacker: String,
+    pub defender: String,
+    pub rounds: Vec<BattleRound>,
+    pub outcome: BattleOutcome,
+    /// destroyed units counted by name
+    pub attacker_losses: BTreeMap<String, u32>,
+    pub defender_losses: BTreeMap<String, u32>,
+    /// surviving units that gained a level, counted by name
+    #[serde(default)]
+    pub attacker_promotions: BTreeMap<String, u32>,
+    #[serde(default)]
+    pub defender_promotions: BTreeMap<String, u32>,
+}
+
+/// Fights rounds between the two fleets until one is destroyed, neither side
+/// can deal damage or `MAX_BATTLE_ROUNDS` is reached.
+///
+/// Both sides fire at the same time, the summed attack of a side destroys enemy
+/// units in order as long as it covers their hit points and wounds the next one
+/// with what is left. Wounds stay, so rounds without losses still wear a tough
+/// unit down. The survivors gain experience afterwards.
+pub fn resolve_battle(
+    attacker_name: &str,
+    attacker: &mut Fleet,
+    defender_name: &str,
+    defender: &mut Fleet,
+) -> BattleReport {
+    let mut report = BattleReport {
+        attacker: attacker_name.to_string(),
+        defender: defender_name.to_string(),
+        rounds: vec![],
+        outcome: BattleOutcome::Draw,
+        attacker_losses: BTreeMap::new(),
+        defender_losses: BTreeMap::new(),
+        attacker_promotions: BTreeMap::new(),
+        defender_promotions: BTreeMap::new(),
+    };
+
+    while (report.rounds.len() as u32) < MAX_BATTLE_ROUNDS
+        && !attacker.ships.is_empty()
+        && !defender.ships.is_empty()
+    {
+        let attacker_damage = total_attack(&attacker.ships);
+        let defender_damage = total_attack(&defender.ships);
+
+        let defender_losses = apply_damage(&mut defender.ships, attacker_damage);
+        let attacker_losses = apply_damage(&mut attacker.ships, defender_damage);
+        let stalemate = attacker_damage == 0 && defender_damage == 0;
+
+        count_losses(&mut report.attacker_losses, &attacker_losses);
+        count_losses(&mut report.defender_losses, &defender_losses);
+        report.rounds.push(BattleRound {
+            attacker_damage,
+            defender_damage,
+            attacker_losses,
+            defender_losses,
+        });
+
+        if stalemate {
+            break;
+        }
+    }
+
+    report.outcome = match (attacker.ships.is_empty(), defender.ships.is_empty()) {
+        (false, true) => BattleOutcome::AttackerWon,
+        (true, false) => BattleOutcome::DefenderWon,
+        _ => BattleOutcome::Draw,
+    };
+
+    let attacker_kills = report.defender_losses.values().sum();
+    let defender_kills = report.attacker_losses.values().sum();
+    report.attacker_promotions = award_experience(&mut attacker.ships, attacker_kills);
+    report.defender_promotions = award_experience(&mut defender.ships, defender_kills);
+
+    report
+}
+
+/// Returns the units that gained a level, counted by name.
+fn award_experience(units: &mut [MilitaryCreationTypes], kills: u32) -> BTreeMap<String, u32> {
+    let mut promotions = BTreeMap::new();
+    for unit in units.iter_mut() {
+        let unit = unit.get_creation_mut();
+        if unit.gain_experience(EXPERIENCE_PER_BATTLE + kills * EXPERIENCE_PER_KILL) {
+            *promotions.entry(unit.name.clone()).or_insert(0) += 1;
+        }
+    }
+    promotions
+}
+
+fn total_attack(units: &[MilitaryCreationTypes]) -> u32 {
+    units.iter().map(|unit| unit.get_creation().attack).sum()
+}
+
+/// Deals `damage` to the units from the front and returns the names of the
+/// destroyed units.
+fn apply_damage(units: &mut Vec<MilitaryCreationTypes>, mut damage: u32) -> Vec<String> {
+    let mut destroyed = 0;
+    for unit in units.iter_mut() {
+        if damage == 0 {
+            break;
+        }
+        damage = unit.get_creation_mut().take_damage(damage);
+        if !unit.get_creation().is_destroyed() {
+            break;
+        }
+        destroyed += 1;
+    }
+
+    units
+        .drain(..destroyed)
+        .map(|unit| unit.get_creation().name.clone())
+        .collect()
+}
+
+fn count_losses(losses: &mut BTreeMap<String, u32>, destroyed: &[String]) {
+    for name in destroyed {
+        *losses.entry(name.clone()).or_insert(0) += 1;
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::buildings::Building;
+use super::error::GameError;
+use super::resource::{Resource, ResourceType};
+
+/// Share of the paid cost given back when a construction is cancelled.
+pub const CANCEL_REFUND_PERCENT: i32 = 50;
+
+#[derive(Serialize, Deserialize)]
+pub struct Construction {
+    pub building: Building,
+    pub ticks_done: u32,
+    pub resources_paid: HashMap<ResourceType, Resource>,
+}
+
+impl Construction {
+    pub fn new(building: Building, resources_paid: HashMap<ResourceType, Resource>) -> Self {
+        Self {
+            building,
+            ticks_done: 0,
+            resources_paid,
+        }
+    }
+
+    pub fn build_time(&self) -> u32 {
+        self.building.get_building_data().build_time
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.ticks_done >= self.build_time()
+    }
+
+    pub fn refund(&self) -> HashMap<ResourceType, Resource> {
+        self.resources_paid
+            .iter()
+            .map(|(resource_type, resource)| {
+                (
+                    *resource_type,
+                    Resource {
+                        resource_type: *resource_type,
+                        amount: resource.amount * CANCEL_REFUND_PERCENT / 100,
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Construction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}/{} ticks)",
+            self.building.get_building_data().name,
+            self.ticks_done,
+            self.build_time()
+        )
+    }
+}
+
+/// Buildings waiting to be built on a planet, only the first one makes progress.
+#[derive(Default, Serialize, Deserialize)]
+pub struct ConstructionQueue {
+    pub constructions: VecDeque<Construction>,
+}
+
+impl ConstructionQueue {
+    pub fn push(&mut self, construction: Construction) {
+        self.constructions.push_back(construction);
+    }
+
+    pub fn len(&self) -> usize {
+        self.constructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.constructions.is_empty()
+    }
+
+    /// Moves the construction at `from` to position `to`, shifting the others.
+    pub fn reorder(&mut self, from: usize, to: usize) -> Result<(), GameError> {
+        if to >= self.constructions.len() {
+            return Err(GameError::UnknownConstruction(to));
+        }
+        let construction = match self.constructions.remove(from) {
+            Some(construction) => construction,
+            None => return Err(GameError::UnknownConstruction(from)),
+        };
+        self.constructions.insert(to, construction);
+        Ok(())
+    }
+
+    pub fn cancel(&mut self, index: usize) -> Result<Construction, GameError> {
+        self.constructions
+            .remove(index)
+            .ok_or(GameError::UnknownConstruction(index))
+    }
+
+    /// Advances the first construction by a tick and returns it once it is finished.
+    pub fn advance(&mut self) -> Option<Construction> {
+        let construction = self.constructions.front_mut()?;
+        construction.ticks_done += 1;
+
+        if construction.is_finished() {
+            self.constructions.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn status(&self) -> Vec<String> {
+        self.constructions
+            .iter()
+            .map(|construction| construction.to_string())
+            .collect()
+    }
+}
@@ -0,0 +1,26 @@
+use mediator_sys::synchronous::basic::*;
+
+use super::{
+    planet::Planet,
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct ConstructionTickRequest<'a> {
+    pub planet: &'a mut Planet,
+}
+
+impl RequestHandler<ConstructionTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: ConstructionTickRequest) {
+        if let Some(construction) = req.planet.construction_queue.advance() {
+            self.publish(TickEvent::new(
+                [req.planet.owner],
+                TickResult::Notification(format!(
+                    "{} has been built on {}",
+                    construction.building.get_building_data().name,
+                    req.planet.name
+                )),
+            ));
+            req.planet.buildings.push(construction.building);
+        }
+    }
+}
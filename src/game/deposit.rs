@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+
+/// A limited source of one resource on a planet, buildings producing that
+/// resource can't extract more than the deposit allows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Deposit {
+    /// what is left to extract, `None` never runs out
+    pub remaining: Option<i32>,
+    /// most that all buildings together can extract in one tick, `None` is unlimited
+    pub max_per_tick: Option<i32>,
+    #[serde(default)]
+    pub extracted_this_tick: i32,
+}
+
+impl Deposit {
+    pub fn new(remaining: Option<i32>, max_per_tick: Option<i32>) -> Self {
+        Self {
+            remaining,
+            max_per_tick,
+            extracted_this_tick: 0,
+        }
+    }
+
+    /// How much of `amount` can be extracted right now.
+    pub fn available(&self, amount: i32) -> i32 {
+        let mut amount = amount;
+        if let Some(remaining) = self.remaining {
+            amount = amount.min(remaining);
+        }
+        if let Some(max_per_tick) = self.max_per_tick {
+            amount = amount.min(max_per_tick - self.extracted_this_tick);
+        }
+        amount.max(0)
+    }
+
+    pub fn extract(&mut self, amount: i32) {
+        self.extracted_this_tick += amount;
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= amount;
+        }
+    }
+
+    pub fn is_depleted(&self) -> bool {
+        self.remaining.is_some_and(|remaining| remaining <= 0)
+    }
+}
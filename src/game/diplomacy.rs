@@ -0,0 +1,171 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::PlayerId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TreatyKind {
+    /// allied fleets never fight and may stay at each other's planets
+    Alliance,
+    /// fleets can't be sent against the other party's planets
+    NonAggression,
+}
+
+impl fmt::Display for TreatyKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Alliance => write!(f, "alliance"),
+            Self::NonAggression => write!(f, "non-aggression pact"),
+        }
+    }
+}
+
+/// How two players stand with each other. Players without a treaty or a war
+/// are neutral, their fleets fight whenever they meet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Neutral,
+    War,
+    Treaty(TreatyKind),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Treaty {
+    pub kind: TreatyKind,
+    /// the two players, the lower id first
+    pub parties: (PlayerId, PlayerId),
+    /// `None` for a treaty that lasts until someone declares war
+    pub ticks_remaining: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proposal {
+    pub id: u32,
+    pub from: PlayerId,
+    pub to: PlayerId,
+    pub kind: TreatyKind,
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diplomacy {
+    pub treaties: Vec<Treaty>,
+    pub proposals: Vec<Proposal>,
+    /// pairs of players at war, the lower id first
+    pub wars: Vec<(PlayerId, PlayerId)>,
+    pub next_proposal_id: u32,
+}
+
+impl Default for Diplomacy {
+    fn default() -> Self {
+        Self {
+            treaties: vec![],
+            proposals: vec![],
+            wars: vec![],
+            next_proposal_id: 1,
+        }
+    }
+}
+
+fn parties(a: PlayerId, b: PlayerId) -> (PlayerId, PlayerId) {
+    (a.min(b), a.max(b))
+}
+
+impl Diplomacy {
+    pub fn treaty(&self, a: PlayerId, b: PlayerId) -> Option<&Treaty> {
+        let pair = parties(a, b);
+        self.treaties.iter().find(|treaty| treaty.parties == pair)
+    }
+
+    pub fn relation(&self, a: PlayerId, b: PlayerId) -> Relation {
+        if let Some(treaty) = self.treaty(a, b) {
+            Relation::Treaty(treaty.kind)
+        } else if self.wars.contains(&parties(a, b)) {
+            Relation::War
+        } else {
+            Relation::Neutral
+        }
+    }
+
+    /// Whether fleets of `a` and `b` fight when they meet. Nobody has a treaty
+    /// with unowned planets and fleets.
+    pub fn is_hostile(&self, a: Option<PlayerId>, b: Option<PlayerId>) -> bool {
+        match (a, b) {
+            (Some(a), Some(b)) => a != b && self.treaty(a, b).is_none(),
+            (a, b) => a != b,
+        }
+    }
+
+    pub fn propose(
+        &mut self,
+        from: PlayerId,
+        to: PlayerId,
+        kind: TreatyKind,
+        duration: Option<u32>,
+    ) -> u32 {
+        let id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        self.proposals.push(Proposal {
+            id,
+            from,
+            to,
+            kind,
+            duration,
+        });
+        id
+    }
+
+    /// Takes a proposal `player` is part of off the table.
+    pub fn remove_proposal(&mut self, player: PlayerId, id: u32) -> Option<Proposal> {
+        let index = self.proposals.iter().position(|proposal| {
+            proposal.id == id && (proposal.from == player || proposal.to == player)
+        })?;
+        Some(self.proposals.remove(index))
+    }
+
+    /// Signs the treaty of `proposal`, it replaces whatever treaty or war the
+    /// two players had.
+    pub fn sign(&mut self, proposal: &Proposal) -> Treaty {
+        let pair = parties(proposal.from, proposal.to);
+        self.treaties.retain(|treaty| treaty.parties != pair);
+        self.wars.retain(|war| *war != pair);
+        let treaty = Treaty {
+            kind: proposal.kind,
+            parties: pair,
+            ticks_remaining: proposal.duration,
+        };
+        self.treaties.push(treaty.clone());
+        treaty
+    }
+
+    /// Ends every treaty and proposal between the two players, returns the
+    /// treaty that was broken.
+    pub fn declare_war(&mut self, from: PlayerId, to: PlayerId) -> Option<Treaty> {
+        let pair = parties(from, to);
+        self.proposals
+            .retain(|proposal| parties(proposal.from, proposal.to) != pair);
+        if !self.wars.contains(&pair) {
+            self.wars.push(pair);
+        }
+        let index = self
+            .treaties
+            .iter()
+            .position(|treaty| treaty.parties == pair)?;
+        Some(self.treaties.remove(index))
+    }
+
+    /// Counts every treaty with a duration down by one tick, returns those that ran out.
+    pub fn advance(&mut self) -> Vec<Treaty> {
+        for treaty in self.treaties.iter_mut() {
+            if let Some(ticks) = treaty.ticks_remaining.as_mut() {
+                *ticks = ticks.saturating_sub(1);
+            }
+        }
+        let (expired, active) = std::mem::take(&mut self.treaties)
+            .into_iter()
+            .partition(|treaty| treaty.ticks_remaining == Some(0));
+        self.treaties = active;
+        expired
+    }
+}
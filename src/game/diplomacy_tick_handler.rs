@@ -0,0 +1,34 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    diplomacy::Diplomacy,
+    player::{Player, PlayerId},
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct DiplomacyTickRequest<'a> {
+    pub diplomacy: &'a mut Diplomacy,
+    pub players: &'a HashMap<PlayerId, Player>,
+}
+
+impl RequestHandler<DiplomacyTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: DiplomacyTickRequest) {
+        let name = |id: PlayerId| match req.players.get(&id) {
+            Some(player) => player.name.clone(),
+            None => id.to_string(),
+        };
+        for treaty in req.diplomacy.advance() {
+            self.publish(TickEvent::new(
+                [Some(treaty.parties.0), Some(treaty.parties.1)],
+                TickResult::Notification(format!(
+                    "The {} between {} and {} has expired",
+                    treaty.kind,
+                    name(treaty.parties.0),
+                    name(treaty.parties.1)
+                )),
+            ));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::player::PlayerId;
+use super::resource::ResourceType;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameError {
+    /// How much of each resource is still missing to pay a cost.
+    InsufficientResources(HashMap<ResourceType, i32>),
+    UnknownResource(ResourceType),
+    /// No configured resource has this id or name.
+    UnknownResourceName(String),
+    UnknownFleet(String),
+    UnknownFleetId(u32),
+    /// The fleet is travelling and can't be changed until it arrives.
+    FleetInTransit(String),
+    /// The two fleets are stationed at different planets.
+    FleetsApart(String, String),
+    SameFleet,
+    /// The fleet doesn't have that many units of the kind.
+    NotEnoughUnits(String, u32),
+    FleetNotEmpty(String),
+    NotMilitaryBuilding(String),
+    BuildingLimitReached(usize),
+    /// A building could not pay its per-tick upkeep and produced nothing.
+    UpkeepUnpaid(String, HashMap<ResourceType, i32>),
+    /// No building at this index on the planet.
+    UnknownBuilding(usize),
+    MaxLevelReached(String, u32),
+    /// No construction at this index in the planet's queue.
+    UnknownConstruction(usize),
+    InvalidCatalog(String),
+    InvalidResourceConfig(String),
+    InvalidResearch(String),
+    UnknownTech(String),
+    AlreadyResearched(String),
+    /// The building or blueprint needs this tech first.
+    TechLocked(String),
+    UnknownBlueprint(String),
+    /// The blueprint can't be built by this building.
+    WrongBlueprint(String, String),
+    UnknownTradeRoute(u32),
+    InvalidTradeRoute(String),
+    UnknownOrder(u32),
+    InvalidOrder(String),
+    UnknownPlayerName(String),
+    UnknownProposal(u32),
+    InvalidProposal(String),
+    /// Players can't sign treaties with or declare war on themselves.
+    SelfDiplomacy,
+    AlreadyAtWar(String),
+    /// The target planet belongs to someone the player has a non-aggression pact with.
+    PactForbidsAttack(String),
+    /// No building with this id in the catalog.
+    UnknownTemplate(String),
+    MissingPrerequisite(String),
+    InvalidSave(String),
+    UnsupportedSaveVersion(u64),
+    UnknownPlayer(PlayerId),
+    PlayerAlreadyRegistered(PlayerId),
+    UnknownPlanet(String),
+    PlanetAlreadyOwned(String),
+    /// The player tried to act on a planet or fleet they don't own.
+    NotOwner(String),
+    FleetAlreadyExists(String),
+    /// Defence turrets are fixed to their planet.
+    ImmobileUnit(String),
+    EmptyFleet(String),
+    /// The fleet is already at the planet it was ordered to.
+    AlreadyThere(String),
+    InvalidGalaxy(String),
+    /// Every planet already has an owner.
+    NoFreePlanet,
+    /// A new galaxy can't be created once players have joined.
+    GameAlreadyStarted,
+    /// Only the game's admin may advance it.
+    NotGameAdmin,
+}
+
+fn format_shortfall(shortfall: &HashMap<ResourceType, i32>) -> String {
+    let mut missing: Vec<String> = shortfall
+        .iter()
+        .map(|(resource_type, amount)| format!("{} {}", amount, resource_type))
+        .collect();
+    missing.sort();
+    missing.join(", ")
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InsufficientResources(shortfall) => {
+                write!(
+                    f,
+                    "Not enough resources, missing {}",
+                    format_shortfall(shortfall)
+                )
+            }
+            Self::UnknownResource(resource_type) => {
+                write!(f, "The planet has no {} resource", resource_type)
+            }
+            Self::UnknownResourceName(name) => {
+                write!(f, "There is no resource called \"{}\"", name)
+            }
+            Self::UnknownFleet(name) => write!(f, "There is no fleet named \"{}\"", name),
+            Self::UnknownFleetId(id) => write!(f, "There is no fleet #{}", id),
+            Self::FleetInTransit(name) => write!(f, "{} is on its way somewhere", name),
+            Self::FleetsApart(a, b) => write!(f, "{} and {} are not at the same planet", a, b),
+            Self::SameFleet => write!(f, "Pick two different fleets"),
+            Self::NotEnoughUnits(unit, count) => {
+                write!(f, "The fleet doesn't have {} {} units", count, unit)
+            }
+            Self::FleetNotEmpty(name) => {
+                write!(f, "{} still has ships, merge or move them first", name)
+            }
+            Self::NotMilitaryBuilding(name) => write!(f, "{} doesn't build ships", name),
+            Self::BuildingLimitReached(limit) => {
+                write!(
+                    f,
+                    "The planet already has the maximum of {} buildings",
+                    limit
+                )
+            }
+            Self::UpkeepUnpaid(building, shortfall) => write!(
+                f,
+                "{} could not pay its upkeep, missing {}",
+                building,
+                format_shortfall(shortfall)
+            ),
+            Self::UnknownBuilding(index) => write!(f, "There is no building #{}", index),
+            Self::MaxLevelReached(building, level) => {
+                write!(f, "{} is already at the maximum level {}", building, level)
+            }
+            Self::UnknownConstruction(index) => {
+                write!(f, "There is no construction #{} in the queue", index)
+            }
+            Self::InvalidCatalog(reason) => write!(f, "Invalid building catalog: {}", reason),
+            Self::InvalidResourceConfig(reason) => {
+                write!(f, "Invalid resource config: {}", reason)
+            }
+            Self::InvalidResearch(reason) => write!(f, "Invalid research tree: {}", reason),
+            Self::UnknownTech(id) => write!(f, "There is no tech called \"{}\"", id),
+            Self::AlreadyResearched(name) => write!(f, "You already researched {}", name),
+            Self::TechLocked(name) => write!(f, "You need to research {} first", name),
+            Self::UnknownBlueprint(id) => write!(f, "There is no blueprint called \"{}\"", id),
+            Self::WrongBlueprint(blueprint, building) => {
+                write!(f, "{} can't be built by {}", blueprint, building)
+            }
+            Self::UnknownTradeRoute(id) => write!(f, "There is no trade route #{}", id),
+            Self::InvalidTradeRoute(reason) => write!(f, "Invalid trade route: {}", reason),
+            Self::UnknownOrder(id) => write!(f, "There is no market order #{}", id),
+            Self::InvalidOrder(reason) => write!(f, "Invalid market order: {}", reason),
+            Self::UnknownPlayerName(name) => write!(f, "There is no player called \"{}\"", name),
+            Self::UnknownProposal(id) => write!(f, "There is no proposal #{}", id),
+            Self::InvalidProposal(reason) => write!(f, "Invalid proposal: {}", reason),
+            Self::SelfDiplomacy => write!(f, "You can't do that with yourself"),
+            Self::AlreadyAtWar(name) => write!(f, "You are already at war with {}", name),
+            Self::PactForbidsAttack(name) => {
+                write!(f, "You have a non-aggression pact with {}", name)
+            }
+            Self::UnknownTemplate(id) => write!(f, "There is no building called \"{}\"", id),
+            Self::MissingPrerequisite(name) => {
+                write!(f, "You need to build {} first", name)
+            }
+            Self::InvalidSave(reason) => write!(f, "Invalid save file: {}", reason),
+            Self::UnsupportedSaveVersion(version) => {
+                write!(f, "Save files of version {} are not supported", version)
+            }
+            Self::UnknownPlayer(_) => write!(f, "You haven't joined the game yet"),
+            Self::PlayerAlreadyRegistered(_) => write!(f, "You have already joined the game"),
+            Self::UnknownPlanet(name) => write!(f, "There is no planet named \"{}\"", name),
+            Self::PlanetAlreadyOwned(name) => write!(f, "{} already has an owner", name),
+            Self::NotOwner(name) => write!(f, "You don't own {}", name),
+            Self::FleetAlreadyExists(name) => {
+                write!(f, "There is already a fleet named \"{}\"", name)
+            }
+            Self::ImmobileUnit(name) => write!(f, "{} can't leave its planet", name),
+            Self::EmptyFleet(name) => write!(f, "{} has no ships", name),
+            Self::AlreadyThere(name) => write!(f, "The fleet is already at {}", name),
+            Self::InvalidGalaxy(reason) => write!(f, "Can't create the galaxy: {}", reason),
+            Self::NoFreePlanet => write!(f, "There is no free planet left"),
+            Self::GameAlreadyStarted => write!(f, "The game has already started"),
+            Self::NotGameAdmin => write!(f, "Only the player running the game can advance it"),
+        }
+    }
+}
+
+impl std::error::Error for GameError {}
@@ -0,0 +1,156 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    capture::{capture_planet, CaptureRules},
+    diplomacy::Diplomacy,
+    error::GameError,
+    movement::FleetMovement,
+    planet::Planet,
+    resource::Resource,
+    siege::{is_hostile, resolve_siege},
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct FleetMovementTickRequest<'a> {
+    pub planets: &'a mut Vec<Planet>,
+    pub movements: &'a mut Vec<FleetMovement>,
+    pub capture_rules: &'a CaptureRules,
+    pub diplomacy: &'a Diplomacy,
+}
+
+impl RequestHandler<FleetMovementTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: FleetMovementTickRequest) {
+        let movements = std::mem::take(req.movements);
+
+        for mut movement in movements {
+            if !movement.advance() {
+                req.movements.push(movement);
+                continue;
+            }
+
+            let origin_position = req
+                .planets
+                .iter()
+                .find(|origin| origin.name == movement.origin)
+                .map(|origin| origin.position.clone());
+            let planet = match req
+                .planets
+                .iter_mut()
+                .find(|planet| planet.name == movement.destination)
+            {
+                Some(planet) => planet,
+                None => {
+                    self.publish(TickEvent::new(
+                        [movement.fleet.owner],
+                        TickResult::Error(GameError::UnknownPlanet(movement.destination)),
+                    ));
+                    continue;
+                }
+            };
+
+            // hostile fleets have to get through the planet's defenders first
+            let mut loot = HashMap::new();
+            let mut repelled = false;
+            if is_hostile(planet, &movement.fleet, req.diplomacy) {
+                let report = resolve_siege(planet, &movement.fleet_name, &mut movement.fleet);
+                let can_capture = report.can_capture;
+                self.publish(TickEvent::new(
+                    [report.attacker_owner, report.defender_owner],
+                    TickResult::Siege(report),
+                ));
+
+                if can_capture {
+                    let report = capture_planet(planet, movement.fleet.owner, req.capture_rules);
+                    loot = report.loot.clone();
+                    self.publish(TickEvent::new(
+                        [report.previous_owner, report.new_owner],
+                        TickResult::Captured(report),
+                    ));
+                } else {
+                    repelled = true;
+                }
+            }
+
+            let planet_name = planet.name.clone();
+            let planet_owner = planet.owner;
+            let fleet_owner = movement.fleet.owner;
+            // survivors of a failed siege can't stay in the enemy's orbit, they
+            // head back to where they came from
+            if let (true, false, Some(origin_position)) =
+                (repelled, movement.fleet.ships.is_empty(), origin_position)
+            {
+                self.publish(TickEvent::new(
+                    [fleet_owner, planet_owner],
+                    TickResult::Notification(format!(
+                        "{} retreats from {} to {}",
+                        movement.fleet_name, planet_name, movement.origin
+                    )),
+                ));
+                req.movements.push(FleetMovement::new(
+                    movement.fleet_name,
+                    movement.fleet,
+                    planet_name,
+                    movement.origin,
+                    planet.position.clone(),
+                    origin_position,
+                ));
+                continue;
+            }
+            if !movement.fleet.ships.is_empty() {
+                let fleet_name = planet.station_fleet(&movement.fleet_name, movement.fleet);
+                self.publish(TickEvent::new(
+                    [fleet_owner, planet_owner],
+                    TickResult::Notification(format!("{} arrived at {}", fleet_name, planet_name)),
+                ));
+            }
+
+            if loot.is_empty() {
+                continue;
+            }
+            // the loot goes back to the planet the fleet came from, if it is still
+            // theirs, otherwise it stays on the captured planet
+            let loot = loot
+                .into_iter()
+                .map(|(resource_type, amount)| {
+                    (
+                        resource_type,
+                        Resource {
+                            resource_type,
+                            amount,
+                        },
+                    )
+                })
+                .collect();
+            let source = format!("Loot from {}", planet_name);
+            let origin = req
+                .planets
+                .iter()
+                .position(|origin| origin.name == movement.origin && origin.owner == fleet_owner);
+            let keeper = match origin {
+                Some(origin) => origin,
+                None => {
+                    self.publish(TickEvent::new(
+                        [fleet_owner],
+                        TickResult::Notification(format!(
+                            "{} lost {}, the loot stays on {}",
+                            movement.fleet_name, movement.origin, planet_name
+                        )),
+                    ));
+                    match req
+                        .planets
+                        .iter()
+                        .position(|planet| planet.name == planet_name)
+                    {
+                        Some(planet) => planet,
+                        None => continue,
+                    }
+                }
+            };
+            if let Err(err) = req.planets[keeper].apply_transaction(&source, &loot) {
+                self.publish(TickEvent::new([fleet_owner], TickResult::Error(err)));
+            }
+        }
+    }
+}
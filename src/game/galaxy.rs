@@ -0,0 +1,173 @@
+use std::collections::{HashMap, HashSet};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::deposit::Deposit;
+use super::error::GameError;
+use super::planet::Planet;
+use super::planet_trait::PlanetTrait;
+use super::position::Position;
+use super::resource::ResourceType;
+
+/// How many random spots are tried for a planet before giving up.
+const MAX_PLACEMENT_ATTEMPTS: u32 = 1000;
+
+/// The largest galaxy that can be generated.
+pub const MAX_PLANETS: usize = 200;
+
+const NAME_PREFIXES: &[&str] = &[
+    "Ar", "Bel", "Cor", "Dra", "El", "Fen", "Gal", "Hy", "Ix", "Jor", "Ka", "Lu", "Mor", "Nex",
+    "Or", "Py", "Qua", "Ry", "Sol", "Tav", "Ul", "Vex", "Wy", "Zan",
+];
+const NAME_SUFFIXES: &[&str] = &["a", "on", "is", "us", "ara", "eth", "ion", "ux", "or", "ia"];
+
+#[derive(Debug, Clone)]
+pub struct GalaxyConfig {
+    pub seed: u64,
+    pub planet_count: usize,
+    pub width: u32,
+    pub height: u32,
+    /// planets are never placed closer than this to each other
+    pub min_distance: u32,
+    /// every resource of a planet starts with an amount in this range
+    pub min_starting_resource: i32,
+    pub max_starting_resource: i32,
+    /// chance out of 100 that a planet gets a trait
+    pub trait_percent: u32,
+    /// chance out of 100 that a resource of a planet comes from a finite deposit
+    pub deposit_percent: u32,
+    pub min_deposit: i32,
+    pub max_deposit: i32,
+    /// deposits can't be extracted faster than this per tick
+    pub deposit_rate: i32,
+}
+
+impl GalaxyConfig {
+    /// A galaxy just big enough to comfortably fit `planet_count` planets.
+    pub fn new(seed: u64, planet_count: usize) -> Self {
+        let min_distance = 10;
+        // a planet count too large for any galaxy gets the largest side,
+        // `generate_galaxy` rejects it anyway
+        let side = ((planet_count as f64).sqrt().ceil() as u32)
+            .checked_add(1)
+            .and_then(|side| side.checked_mul(min_distance * 2))
+            .unwrap_or(u32::MAX);
+        Self {
+            seed,
+            planet_count,
+            width: side,
+            height: side,
+            min_distance,
+            min_starting_resource: 0,
+            max_starting_resource: 50,
+            trait_percent: 30,
+            deposit_percent: 25,
+            min_deposit: 200,
+            max_deposit: 1000,
+            deposit_rate: 40,
+        }
+    }
+}
+
+/// Builds the planets of a new galaxy, the same config always gives the same galaxy.
+pub fn generate_galaxy(config: &GalaxyConfig) -> Result<Vec<Planet>, GameError> {
+    if config.planet_count > MAX_PLANETS {
+        return Err(GameError::InvalidGalaxy(format!(
+            "a galaxy can't have more than {} planets",
+            MAX_PLANETS
+        )));
+    }
+    if config.min_starting_resource > config.max_starting_resource {
+        return Err(GameError::InvalidGalaxy(String::from(
+            "the minimum starting resource is above the maximum",
+        )));
+    }
+    if config.min_deposit > config.max_deposit {
+        return Err(GameError::InvalidGalaxy(String::from(
+            "the minimum deposit is above the maximum",
+        )));
+    }
+
+    let mut rng = StdRng::seed_from_u64(config.seed);
+    let mut names = HashSet::new();
+    let mut planets: Vec<Planet> = vec![];
+
+    for _ in 0..config.planet_count {
+        let position = place_planet(&mut rng, config, &planets).ok_or_else(|| {
+            GameError::InvalidGalaxy(format!(
+                "{} planets don't fit in a {}x{} galaxy",
+                config.planet_count, config.width, config.height
+            ))
+        })?;
+
+        let mut planet = Planet::new(planet_name(&mut rng, &mut names), position.x, position.y);
+        // sorted so the same seed always rolls the same numbers for the same resource
+        let mut resource_types: Vec<ResourceType> = planet.resources.keys().copied().collect();
+        resource_types.sort_by_key(|resource_type| resource_type.id());
+        for resource_type in resource_types {
+            if let Some(resource) = planet.resources.get_mut(&resource_type) {
+                resource.amount =
+                    rng.gen_range(config.min_starting_resource..=config.max_starting_resource);
+            }
+            if rng.gen_range(0..100) < config.deposit_percent {
+                let remaining = rng.gen_range(config.min_deposit..=config.max_deposit);
+                planet.deposits.insert(
+                    resource_type,
+                    Deposit::new(Some(remaining), Some(config.deposit_rate)),
+                );
+            }
+        }
+        if rng.gen_range(0..100) < config.trait_percent {
+            planet
+                .traits
+                .push(PlanetTrait::ALL[rng.gen_range(0..PlanetTrait::ALL.len())]);
+        }
+        planets.push(planet);
+    }
+
+    Ok(planets)
+}
+
+/// Inhabitants a player finds on their home planet when they join.
+pub const HOME_PLANET_POPULATION: u32 = 10;
+
+/// Resources a player finds on their home planet when they join.
+pub fn home_planet_resources() -> HashMap<ResourceType, i32> {
+    HashMap::from([
+        (ResourceType::Credits, 100),
+        (ResourceType::Food, 50),
+        (ResourceType::Minerals, 50),
+    ])
+}
+
+fn place_planet(rng: &mut StdRng, config: &GalaxyConfig, planets: &[Planet]) -> Option<Position> {
+    (0..MAX_PLACEMENT_ATTEMPTS)
+        .map(|_| Position {
+            x: rng.gen_range(0..=config.width),
+            y: rng.gen_range(0..=config.height),
+        })
+        .find(|position| {
+            planets
+                .iter()
+                .all(|planet| planet.position.distance(position) >= config.min_distance as f64)
+        })
+}
+
+/// A random single word name, numbered when it was already taken.
+fn planet_name(rng: &mut StdRng, names: &mut HashSet<String>) -> String {
+    let base = format!(
+        "{}{}",
+        NAME_PREFIXES[rng.gen_range(0..NAME_PREFIXES.len())],
+        NAME_SUFFIXES[rng.gen_range(0..NAME_SUFFIXES.len())]
+    );
+
+    let mut name = base.clone();
+    let mut number = 2;
+    while names.contains(&name) {
+        name = format!("{}-{}", base, number);
+        number += 1;
+    }
+    names.insert(name.clone());
+    name
+}
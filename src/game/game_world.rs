@@ -1,30 +1,954 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
 use mediator_sys::builder::{BuilderFlow, BuilderInternal};
-use mediator_sys::synchronous::basic::{BasicMediator, SyncMediatorInternalHandle};
+use mediator_sys::synchronous::basic::{
+    BasicMediator, SyncMediatorInternalHandle, SyncMediatorInternalNext,
+};
 
 use super::build_tick_handler::BuildingTickRequest;
+use super::buildings::Building;
+use super::capture::CaptureRules;
+use super::catalog::BuildingCatalog;
+use super::construction_tick_handler::ConstructionTickRequest;
+use super::diplomacy::{Diplomacy, Proposal, Relation, Treaty, TreatyKind};
+use super::diplomacy_tick_handler::DiplomacyTickRequest;
+use super::error::GameError;
+use super::fleet_movement_handler::FleetMovementTickRequest;
+use super::galaxy::{generate_galaxy, home_planet_resources, GalaxyConfig, HOME_PLANET_POPULATION};
+use super::market::{Market, Order, OrderSide};
+use super::market_tick_handler::MarketTickRequest;
+use super::military::{Fleet, FleetId, MilitaryCreationTypes};
+use super::movement::FleetMovement;
 use super::planet::Planet;
-use super::tickable::{TickResult, Tickable};
+use super::player::{Player, PlayerId};
+use super::population_tick_handler::PopulationTickRequest;
+use super::repair_tick_handler::RepairTickRequest;
+use super::research::{ResearchTree, Tech, TechStatus};
+use super::research_tick_handler::ResearchTickRequest;
+use super::resource::{resource_map, ResourceType};
+use super::resource_registry::ResourceRegistry;
+use super::tickable::{TickEvent, TickResult, Tickable};
+use super::trade::{Convoy, TradeRoute};
+use super::trade_tick_handler::TradeTickRequest;
+
+/// Tick events kept for players who haven't collected them yet, the oldest go first.
+pub const MAX_QUEUED_EVENTS: usize = 1000;
 
 pub struct GameWorld {
     pub planets: Vec<Planet>,
-    pub tick_mediator: BasicMediator<TickResult>,
+    pub tick_mediator: BasicMediator<TickEvent>,
+    pub catalog: BuildingCatalog,
+    pub players: HashMap<PlayerId, Player>,
+    pub movements: Vec<FleetMovement>,
+    pub capture_rules: CaptureRules,
+    /// change it with `set_resources` so every planet gets the new types
+    pub resources: ResourceRegistry,
+    pub research: ResearchTree,
+    pub trade_routes: Vec<TradeRoute>,
+    pub convoys: Vec<Convoy>,
+    pub next_trade_route_id: u32,
+    pub market: Market,
+    pub diplomacy: Diplomacy,
+    pub next_fleet_id: FleetId,
+    /// the player who created the game or joined it first, the only one who may tick it
+    pub admin: Option<PlayerId>,
+    events: Arc<Mutex<Vec<TickEvent>>>,
 }
 
 impl GameWorld {
     pub fn new(planets: Vec<Planet>) -> Self {
-        Self {
+        let events = Arc::new(Mutex::new(vec![]));
+        let listener_events = Arc::clone(&events);
+
+        let resources = ResourceRegistry::default();
+        let mut planets = planets;
+        for planet in planets.iter_mut() {
+            resources.init_planet(planet);
+        }
+
+        let mut world = Self {
             planets,
-            tick_mediator: BasicMediator::<TickResult>::builder().build(),
+            tick_mediator: BasicMediator::<TickEvent>::builder()
+                .add_listener(move |event| {
+                    if let Ok(mut events) = listener_events.lock() {
+                        events.push(event);
+                        // players who never collect their events don't keep them forever
+                        if events.len() > MAX_QUEUED_EVENTS {
+                            events.remove(0);
+                        }
+                    }
+                })
+                .build(),
+            catalog: BuildingCatalog::default(),
+            players: HashMap::new(),
+            movements: vec![],
+            capture_rules: CaptureRules::default(),
+            resources,
+            research: ResearchTree::default(),
+            trade_routes: vec![],
+            convoys: vec![],
+            next_trade_route_id: 1,
+            market: Market::default(),
+            diplomacy: Diplomacy::default(),
+            next_fleet_id: 1,
+            admin: None,
+            events,
+        };
+        world.assign_fleet_ids();
+        world
+    }
+
+    /// Gives every fleet formed by the planets themselves an id, call it after
+    /// adding planets. Ticks do it for the fleets they form.
+    pub fn assign_fleet_ids(&mut self) {
+        let fleets = self
+            .planets
+            .iter_mut()
+            .flat_map(|planet| planet.military.values_mut())
+            .chain(
+                self.movements
+                    .iter_mut()
+                    .map(|movement| &mut movement.fleet),
+            );
+        let mut unassigned = vec![];
+        for fleet in fleets {
+            if fleet.id == 0 {
+                unassigned.push(fleet);
+            } else {
+                self.next_fleet_id = self.next_fleet_id.max(fleet.id + 1);
+            }
+        }
+        for fleet in unassigned {
+            fleet.id = self.next_fleet_id;
+            self.next_fleet_id += 1;
+        }
+    }
+
+    /// A world on a freshly generated galaxy.
+    pub fn from_galaxy(config: &GalaxyConfig) -> Result<Self, GameError> {
+        Ok(Self::new(generate_galaxy(config)?))
+    }
+
+    /// Replaces the building catalog, every resource it uses must be registered.
+    pub fn set_catalog(&mut self, catalog: BuildingCatalog) -> Result<(), GameError> {
+        catalog.check_resources(&self.resources)?;
+        self.catalog = catalog;
+        Ok(())
+    }
+
+    /// Replaces the tech tree, everything it unlocks must be in the catalog and
+    /// every resource it boosts must be registered.
+    pub fn set_research(&mut self, research: ResearchTree) -> Result<(), GameError> {
+        research.check_against(&self.catalog, &self.resources)?;
+        self.research = research;
+        Ok(())
+    }
+
+    /// Switches to the resource types of `registry`, every planet starts the
+    /// types it doesn't have yet at 0.
+    pub fn set_resources(&mut self, registry: ResourceRegistry) {
+        self.resources = registry;
+        self.init_planet_resources();
+    }
+
+    /// Gives every planet all registered resource types, call it after adding planets.
+    pub fn init_planet_resources(&mut self) {
+        for planet in self.planets.iter_mut() {
+            self.resources.init_planet(planet);
+        }
+    }
+
+    pub fn register_player(&mut self, id: PlayerId, name: String) -> Result<(), GameError> {
+        if self.players.contains_key(&id) {
+            return Err(GameError::PlayerAlreadyRegistered(id));
+        }
+        self.players.insert(id, Player::new(id, name));
+        self.admin.get_or_insert(id);
+        Ok(())
+    }
+
+    pub fn get_player(&self, id: PlayerId) -> Result<&Player, GameError> {
+        self.players.get(&id).ok_or(GameError::UnknownPlayer(id))
+    }
+
+    pub fn get_planet(&self, name: &str) -> Result<&Planet, GameError> {
+        self.planets
+            .iter()
+            .find(|planet| planet.name == name)
+            .ok_or_else(|| GameError::UnknownPlanet(name.to_string()))
+    }
+
+    pub fn get_planet_mut(&mut self, name: &str) -> Result<&mut Planet, GameError> {
+        self.planets
+            .iter_mut()
+            .find(|planet| planet.name == name)
+            .ok_or_else(|| GameError::UnknownPlanet(name.to_string()))
+    }
+
+    pub fn planets_of(&self, player: PlayerId) -> impl Iterator<Item = &Planet> {
+        self.planets
+            .iter()
+            .filter(move |planet| planet.owner == Some(player))
+    }
+
+    /// Gives an unowned planet to a registered player.
+    pub fn claim_planet(&mut self, player: PlayerId, planet_name: &str) -> Result<(), GameError> {
+        self.get_player(player)?;
+        let planet = self.get_planet_mut(planet_name)?;
+        if planet.owner.is_some() {
+            return Err(GameError::PlanetAlreadyOwned(planet.name.clone()));
+        }
+        planet.set_owner(Some(player));
+        Ok(())
+    }
+
+    /// Gives `player` the free planet farthest away from every owned planet,
+    /// stocked with the home planet resources, and returns its name.
+    pub fn assign_home_planet(&mut self, player: PlayerId) -> Result<String, GameError> {
+        self.get_player(player)?;
+        let owned: Vec<_> = self
+            .planets
+            .iter()
+            .filter(|planet| planet.owner.is_some())
+            .map(|planet| planet.position.clone())
+            .collect();
+
+        let mut home: Option<(usize, f64)> = None;
+        for (index, planet) in self.planets.iter().enumerate() {
+            if planet.owner.is_some() {
+                continue;
+            }
+            let distance = owned
+                .iter()
+                .map(|position| planet.position.distance(position))
+                .fold(f64::INFINITY, f64::min);
+            if home.is_none_or(|(_, best)| distance > best) {
+                home = Some((index, distance));
+            }
+        }
+
+        let planet = match home {
+            Some((index, _)) => &mut self.planets[index],
+            None => return Err(GameError::NoFreePlanet),
+        };
+        planet.apply_transaction("Home planet", &resource_map(&home_planet_resources()))?;
+        planet.set_owner(Some(player));
+        planet.population.size = planet.population.size.max(HOME_PLANET_POPULATION);
+        Ok(planet.name.clone())
+    }
+
+    /// The planet `planet_name` if it belongs to `player`.
+    pub fn owned_planet_mut(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+    ) -> Result<&mut Planet, GameError> {
+        self.get_player(player)?;
+        let planet = self.get_planet_mut(planet_name)?;
+        planet.authorize(player)?;
+        Ok(planet)
+    }
+
+    pub fn build_building(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        building: Building,
+    ) -> Result<(), GameError> {
+        self.owned_planet_mut(player, planet_name)?
+            .build_building(building)
+    }
+
+    pub fn build_from_catalog(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        id: &str,
+    ) -> Result<(), GameError> {
+        let research = &self.get_player(player)?.research;
+        if let Some(tech) = self.research.unlocking_building(id) {
+            if !research.has(&tech.id) {
+                return Err(GameError::TechLocked(tech.name.clone()));
+            }
+        }
+        let catalog = &self.catalog;
+        let planet = self
+            .planets
+            .iter_mut()
+            .find(|planet| planet.name == planet_name)
+            .ok_or_else(|| GameError::UnknownPlanet(planet_name.to_string()))?;
+        planet.authorize(player)?;
+        planet.build_from_catalog(catalog, id)
+    }
+
+    /// Switches the military building at `index` to build the catalog blueprint `id`.
+    pub fn refit_building(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        index: usize,
+        id: &str,
+    ) -> Result<(), GameError> {
+        let blueprint = self
+            .catalog
+            .get_blueprint(id)
+            .ok_or_else(|| GameError::UnknownBlueprint(id.to_string()))?;
+        let research = &self.get_player(player)?.research;
+        if let Some(tech) = self.research.unlocking_blueprint(id) {
+            if !research.has(&tech.id) {
+                return Err(GameError::TechLocked(tech.name.clone()));
+            }
+        }
+        let building_name = self
+            .catalog
+            .get(&blueprint.building)
+            .map(|template| template.name.clone())
+            .ok_or_else(|| GameError::UnknownTemplate(blueprint.building.clone()))?;
+        let building_id = blueprint.building.clone();
+        let unit = blueprint.unit.clone();
+
+        let planet = self.owned_planet_mut(player, planet_name)?;
+        let building = planet
+            .buildings
+            .get_mut(index)
+            .ok_or(GameError::UnknownBuilding(index))?;
+        match building {
+            Building::MilitaryBuilding(mil)
+                if mil.building.is_template(&building_id, &building_name) =>
+            {
+                mil.create_type = unit.create();
+                mil.military_cost_per_tick = resource_map(&unit.upkeep);
+                mil.progress_required_to_create_military = unit.progress_required;
+                mil.progress_increase_per_tick = unit.progress_per_tick;
+                mil.current_progress = 0;
+                Ok(())
+            }
+            building => Err(GameError::WrongBlueprint(
+                unit.name,
+                building.get_building_data().name.clone(),
+            )),
+        }
+    }
+
+    /// Every tech of the research tree with where `player` stands on it.
+    pub fn techs_of(&self, player: PlayerId) -> Result<Vec<(&Tech, TechStatus)>, GameError> {
+        let research = &self.get_player(player)?.research;
+        Ok(self
+            .research
+            .techs
+            .iter()
+            .map(|tech| (tech, research.status(tech)))
+            .collect())
+    }
+
+    /// Techs `player` can start researching now.
+    pub fn available_techs(&self, player: PlayerId) -> Result<Vec<&Tech>, GameError> {
+        Ok(self
+            .techs_of(player)?
+            .into_iter()
+            .filter(|(_, status)| *status == TechStatus::Available)
+            .map(|(tech, _)| tech)
+            .collect())
+    }
+
+    /// Techs `player` can't research until they finish their prerequisites.
+    pub fn locked_techs(&self, player: PlayerId) -> Result<Vec<&Tech>, GameError> {
+        Ok(self
+            .techs_of(player)?
+            .into_iter()
+            .filter(|(_, status)| *status == TechStatus::Locked)
+            .map(|(tech, _)| tech)
+            .collect())
+    }
+
+    /// Puts research points into the tech `id` from now on, progress on the
+    /// previous tech is lost.
+    pub fn start_research(&mut self, player: PlayerId, id: &str) -> Result<(), GameError> {
+        let tech = self
+            .research
+            .get(id)
+            .ok_or_else(|| GameError::UnknownTech(id.to_string()))?;
+        let player = self
+            .players
+            .get_mut(&player)
+            .ok_or(GameError::UnknownPlayer(player))?;
+
+        match player.research.status(tech) {
+            TechStatus::Researched => Err(GameError::AlreadyResearched(tech.name.clone())),
+            TechStatus::Researching(_) => Ok(()),
+            TechStatus::Locked => {
+                let missing = tech
+                    .prerequisites
+                    .iter()
+                    .find(|prerequisite| !player.research.has(prerequisite))
+                    .and_then(|prerequisite| self.research.get(prerequisite))
+                    .map_or_else(|| tech.name.clone(), |missing| missing.name.clone());
+                Err(GameError::TechLocked(missing))
+            }
+            TechStatus::Available => {
+                player.research.current = Some(tech.id.clone());
+                player.research.progress = 0;
+                Ok(())
+            }
+        }
+    }
+
+    /// Creates an empty fleet on `planet_name`, returns its id.
+    pub fn create_fleet(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        fleet_name: String,
+    ) -> Result<FleetId, GameError> {
+        let id = self.next_fleet_id;
+        let planet = self.owned_planet_mut(player, planet_name)?;
+        if planet.military.contains_key(&fleet_name) {
+            return Err(GameError::FleetAlreadyExists(fleet_name));
+        }
+        let mut fleet = Fleet::new(Some(player));
+        fleet.id = id;
+        fleet.position = planet.position.clone();
+        planet.military.insert(fleet_name, fleet);
+        self.next_fleet_id += 1;
+        Ok(id)
+    }
+
+    /// The index of the planet the fleet `id` of `player` is stationed at and
+    /// the name it is stationed under.
+    pub fn locate_fleet(
+        &self,
+        player: PlayerId,
+        id: FleetId,
+    ) -> Result<(usize, String), GameError> {
+        self.get_player(player)?;
+        if let Some(movement) = self
+            .movements
+            .iter()
+            .find(|movement| movement.fleet.id == id)
+        {
+            if movement.fleet.owner != Some(player) {
+                return Err(GameError::NotOwner(movement.fleet_name.clone()));
+            }
+            return Err(GameError::FleetInTransit(movement.fleet_name.clone()));
+        }
+        for (index, planet) in self.planets.iter().enumerate() {
+            if let Some(name) = planet.fleet_name_of(id) {
+                if planet.military[&name].owner != Some(player) {
+                    return Err(GameError::NotOwner(name));
+                }
+                return Ok((index, name));
+            }
+        }
+        Err(GameError::UnknownFleetId(id))
+    }
+
+    pub fn rename_fleet(
+        &mut self,
+        player: PlayerId,
+        id: FleetId,
+        new_name: String,
+    ) -> Result<(), GameError> {
+        let (index, name) = self.locate_fleet(player, id)?;
+        let planet = &mut self.planets[index];
+        if planet.military.contains_key(&new_name) {
+            return Err(GameError::FleetAlreadyExists(new_name));
+        }
+        if let Some(fleet) = planet.military.remove(&name) {
+            planet.military.insert(new_name, fleet);
+        }
+        Ok(())
+    }
+
+    /// Moves every ship of the fleet `from` into `into` and disbands `from`.
+    pub fn merge_fleets(
+        &mut self,
+        player: PlayerId,
+        from: FleetId,
+        into: FleetId,
+    ) -> Result<(), GameError> {
+        if from == into {
+            return Err(GameError::SameFleet);
+        }
+        let (from_planet, from_name) = self.locate_fleet(player, from)?;
+        let (into_planet, into_name) = self.locate_fleet(player, into)?;
+        if from_planet != into_planet {
+            return Err(GameError::FleetsApart(from_name, into_name));
+        }
+
+        let planet = &mut self.planets[from_planet];
+        let from = planet
+            .military
+            .remove(&from_name)
+            .ok_or(GameError::UnknownFleet(from_name))?;
+        let into = planet
+            .military
+            .get_mut(&into_name)
+            .ok_or(GameError::UnknownFleet(into_name))?;
+        into.ships.extend(from.ships);
+        // the merged fleet moves at the pace of the slower one
+        into.speed = into.speed.min(from.speed);
+        Ok(())
+    }
+
+    /// Moves `count` units called `unit` out of the fleet `id` into a new fleet
+    /// `new_name` on the same planet, returns the new fleet's id.
+    pub fn split_fleet(
+        &mut self,
+        player: PlayerId,
+        id: FleetId,
+        unit: &str,
+        count: u32,
+        new_name: String,
+    ) -> Result<FleetId, GameError> {
+        let (index, name) = self.locate_fleet(player, id)?;
+        let new_id = self.next_fleet_id;
+        let planet = &mut self.planets[index];
+        if planet.military.contains_key(&new_name) {
+            return Err(GameError::FleetAlreadyExists(new_name));
+        }
+        let fleet = planet
+            .military
+            .get_mut(&name)
+            .ok_or(GameError::UnknownFleet(name))?;
+        let matches =
+            |ship: &MilitaryCreationTypes| ship.get_creation().name.eq_ignore_ascii_case(unit);
+        if count == 0 || fleet.ships.iter().filter(|ship| matches(ship)).count() < count as usize {
+            return Err(GameError::NotEnoughUnits(unit.to_string(), count));
+        }
+
+        let mut split = Fleet::new(fleet.owner);
+        split.id = new_id;
+        split.position = fleet.position.clone();
+        split.speed = fleet.speed;
+        let mut remaining = count;
+        fleet.ships.retain(|ship| {
+            if remaining == 0 || !matches(ship) {
+                return true;
+            }
+            split.ships.push(ship.clone());
+            remaining -= 1;
+            false
+        });
+        planet.military.insert(new_name, split);
+        self.next_fleet_id += 1;
+        Ok(new_id)
+    }
+
+    /// Removes an empty fleet.
+    pub fn disband_fleet(&mut self, player: PlayerId, id: FleetId) -> Result<(), GameError> {
+        let (index, name) = self.locate_fleet(player, id)?;
+        let planet = &mut self.planets[index];
+        if !planet.military[&name].ships.is_empty() {
+            return Err(GameError::FleetNotEmpty(name));
+        }
+        planet.military.remove(&name);
+        Ok(())
+    }
+
+    /// Sends the ships built by building `index` on `planet_name` to the fleet
+    /// `fleet`, or back to the planet's default fleet for `None`.
+    pub fn set_rally_fleet(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        index: usize,
+        fleet: Option<FleetId>,
+    ) -> Result<(), GameError> {
+        if let Some(fleet) = fleet {
+            let (fleet_planet, fleet_name) = self.locate_fleet(player, fleet)?;
+            if self.planets[fleet_planet].name != planet_name {
+                return Err(GameError::FleetsApart(fleet_name, planet_name.to_string()));
+            }
+        }
+        let planet = self.owned_planet_mut(player, planet_name)?;
+        match planet.buildings.get_mut(index) {
+            Some(Building::MilitaryBuilding(mil)) => {
+                mil.rally_fleet = fleet;
+                Ok(())
+            }
+            Some(building) => Err(GameError::NotMilitaryBuilding(
+                building.get_building_data().name.clone(),
+            )),
+            None => Err(GameError::UnknownBuilding(index)),
+        }
+    }
+
+    /// The fleet `fleet_name` stationed on `planet_name` if it belongs to `player`.
+    pub fn owned_fleet_mut(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        fleet_name: &str,
+    ) -> Result<&mut Fleet, GameError> {
+        self.get_player(player)?;
+        let fleet = self
+            .get_planet_mut(planet_name)?
+            .military
+            .get_mut(fleet_name)
+            .ok_or_else(|| GameError::UnknownFleet(fleet_name.to_string()))?;
+        if fleet.owner != Some(player) {
+            return Err(GameError::NotOwner(fleet_name.to_string()));
+        }
+        Ok(fleet)
+    }
+
+    /// Sends the fleet from its planet towards `target`, returns the travel time in ticks.
+    pub fn move_fleet(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        fleet_name: &str,
+        target: &str,
+    ) -> Result<u32, GameError> {
+        if planet_name == target {
+            return Err(GameError::AlreadyThere(target.to_string()));
+        }
+        let target_planet = self.get_planet(target)?;
+        let to = target_planet.position.clone();
+        if let Some(target_owner) = target_planet.owner {
+            if self.diplomacy.relation(player, target_owner)
+                == Relation::Treaty(TreatyKind::NonAggression)
+            {
+                return Err(GameError::PactForbidsAttack(
+                    self.get_player(target_owner)?.name.clone(),
+                ));
+            }
+        }
+
+        if self
+            .owned_fleet_mut(player, planet_name, fleet_name)?
+            .ships
+            .is_empty()
+        {
+            return Err(GameError::EmptyFleet(fleet_name.to_string()));
+        }
+
+        let planet = self.get_planet_mut(planet_name)?;
+        let from = planet.position.clone();
+        let fleet = match planet.military.remove(fleet_name) {
+            Some(fleet) => fleet,
+            None => return Err(GameError::UnknownFleet(fleet_name.to_string())),
+        };
+        let movement = FleetMovement::new(
+            fleet_name.to_string(),
+            fleet,
+            planet_name.to_string(),
+            target.to_string(),
+            from,
+            to,
+        );
+        let ticks = movement.total_ticks;
+        self.movements.push(movement);
+
+        Ok(ticks)
+    }
+
+    pub fn movements_of(&self, player: PlayerId) -> impl Iterator<Item = &FleetMovement> {
+        self.movements
+            .iter()
+            .filter(move |movement| movement.fleet.owner == Some(player))
+    }
+
+    /// Ships `cargo` from the planet `origin` of `player` to `destination` every
+    /// `interval` ticks, the first convoy leaves on the next tick. Returns the route id.
+    pub fn create_trade_route(
+        &mut self,
+        player: PlayerId,
+        origin: &str,
+        destination: &str,
+        cargo: HashMap<ResourceType, i32>,
+        interval: u32,
+    ) -> Result<u32, GameError> {
+        if origin == destination {
+            return Err(GameError::InvalidTradeRoute(String::from(
+                "a route needs two different planets",
+            )));
+        }
+        if interval == 0 {
+            return Err(GameError::InvalidTradeRoute(String::from(
+                "convoys need at least one tick between them",
+            )));
+        }
+        if cargo.is_empty() || cargo.values().any(|amount| *amount <= 0) {
+            return Err(GameError::InvalidTradeRoute(String::from(
+                "every cargo amount must be positive",
+            )));
+        }
+        let resource_types = self.resources.resource_types();
+        if let Some(resource_type) = cargo
+            .keys()
+            .find(|resource_type| !resource_types.contains(resource_type))
+        {
+            return Err(GameError::UnknownResource(*resource_type));
+        }
+        self.get_planet(destination)?;
+        self.owned_planet_mut(player, origin)?;
+
+        let id = self.next_trade_route_id;
+        self.next_trade_route_id += 1;
+        self.trade_routes.push(TradeRoute {
+            id,
+            owner: player,
+            origin: origin.to_string(),
+            destination: destination.to_string(),
+            cargo,
+            interval,
+            ticks_until_departure: 1,
+        });
+        Ok(id)
+    }
+
+    /// Stops the route from sending more convoys, those on their way still arrive.
+    pub fn cancel_trade_route(&mut self, player: PlayerId, id: u32) -> Result<(), GameError> {
+        self.get_player(player)?;
+        let index = self
+            .trade_routes
+            .iter()
+            .position(|route| route.id == id && route.owner == player)
+            .ok_or(GameError::UnknownTradeRoute(id))?;
+        self.trade_routes.remove(index);
+        Ok(())
+    }
+
+    pub fn trade_routes_of(&self, player: PlayerId) -> impl Iterator<Item = &TradeRoute> {
+        self.trade_routes
+            .iter()
+            .filter(move |route| route.owner == player)
+    }
+
+    pub fn convoys_of(&self, player: PlayerId) -> impl Iterator<Item = &Convoy> {
+        self.convoys
+            .iter()
+            .filter(move |convoy| convoy.owner == player)
+    }
+
+    /// Puts an order for `quantity` of `resource_type` at `price` Credits each on
+    /// the market and pays its escrow from `planet_name`. Returns the order id.
+    pub fn place_order(
+        &mut self,
+        player: PlayerId,
+        planet_name: &str,
+        side: OrderSide,
+        resource_type: ResourceType,
+        quantity: i32,
+        price: i32,
+    ) -> Result<u32, GameError> {
+        if quantity <= 0 || price <= 0 {
+            return Err(GameError::InvalidOrder(String::from(
+                "quantity and price must be positive",
+            )));
+        }
+        if resource_type == ResourceType::Credits {
+            return Err(GameError::InvalidOrder(String::from(
+                "Credits can't be traded for Credits",
+            )));
+        }
+        if quantity.checked_mul(price).is_none() {
+            return Err(GameError::InvalidOrder(String::from(
+                "the order is worth too many Credits",
+            )));
+        }
+        if !self.resources.resource_types().contains(&resource_type) {
+            return Err(GameError::UnknownResource(resource_type));
+        }
+
+        let order = Order {
+            id: self.market.next_order_id,
+            owner: player,
+            planet: planet_name.to_string(),
+            side,
+            resource_type,
+            quantity,
+            price,
+        };
+        let (escrow_type, escrow) = order.escrow();
+        let source = format!("Market order #{}", order.id);
+        self.owned_planet_mut(player, planet_name)?
+            .apply_transaction(
+                &source,
+                &resource_map(&HashMap::from([(escrow_type, -escrow)])),
+            )?;
+        Ok(self
+            .market
+            .add_order(player, planet_name, side, resource_type, quantity, price))
+    }
+
+    /// Takes the order off the market and returns what is left of its escrow.
+    pub fn cancel_order(&mut self, player: PlayerId, id: u32) -> Result<(), GameError> {
+        self.get_player(player)?;
+        let order = self
+            .market
+            .remove_order(player, id)
+            .ok_or(GameError::UnknownOrder(id))?;
+        let (escrow_type, escrow) = order.escrow();
+        let source = format!("Market order #{}", order.id);
+        self.get_planet_mut(&order.planet)?.apply_transaction(
+            &source,
+            &resource_map(&HashMap::from([(escrow_type, escrow)])),
+        )
+    }
+
+    pub fn orders_of(&self, player: PlayerId) -> impl Iterator<Item = &Order> {
+        self.market
+            .orders
+            .iter()
+            .filter(move |order| order.owner == player)
+    }
+
+    pub fn find_player(&self, name: &str) -> Result<PlayerId, GameError> {
+        self.players
+            .values()
+            .find(|player| player.name.eq_ignore_ascii_case(name))
+            .map(|player| player.id)
+            .ok_or_else(|| GameError::UnknownPlayerName(name.to_string()))
+    }
+
+    /// Offers `target` a treaty, it is signed once they accept it. A treaty
+    /// without a duration lasts until one of them declares war.
+    pub fn propose_treaty(
+        &mut self,
+        player: PlayerId,
+        target: PlayerId,
+        kind: TreatyKind,
+        duration: Option<u32>,
+    ) -> Result<u32, GameError> {
+        self.get_player(player)?;
+        let target_name = self.get_player(target)?.name.clone();
+        if player == target {
+            return Err(GameError::SelfDiplomacy);
+        }
+        if duration == Some(0) {
+            return Err(GameError::InvalidProposal(String::from(
+                "a treaty has to last at least one tick",
+            )));
+        }
+        if self.diplomacy.relation(player, target) == Relation::Treaty(kind) {
+            return Err(GameError::InvalidProposal(format!(
+                "you already have a {} with {}",
+                kind, target_name
+            )));
+        }
+        Ok(self.diplomacy.propose(player, target, kind, duration))
+    }
+
+    /// Signs a treaty proposed to `player`.
+    pub fn accept_proposal(&mut self, player: PlayerId, id: u32) -> Result<Treaty, GameError> {
+        self.get_player(player)?;
+        let index = self
+            .diplomacy
+            .proposals
+            .iter()
+            .position(|proposal| proposal.id == id && proposal.to == player)
+            .ok_or(GameError::UnknownProposal(id))?;
+        let proposal = self.diplomacy.proposals.remove(index);
+        Ok(self.diplomacy.sign(&proposal))
+    }
+
+    /// Declines a proposal made to `player` or withdraws one they made.
+    pub fn reject_proposal(&mut self, player: PlayerId, id: u32) -> Result<Proposal, GameError> {
+        self.get_player(player)?;
+        self.diplomacy
+            .remove_proposal(player, id)
+            .ok_or(GameError::UnknownProposal(id))
+    }
+
+    /// Breaks any treaty with `target`, returns the broken treaty.
+    pub fn declare_war(
+        &mut self,
+        player: PlayerId,
+        target: PlayerId,
+    ) -> Result<Option<Treaty>, GameError> {
+        self.get_player(player)?;
+        let target_name = self.get_player(target)?.name.clone();
+        if player == target {
+            return Err(GameError::SelfDiplomacy);
+        }
+        if self.diplomacy.relation(player, target) == Relation::War {
+            return Err(GameError::AlreadyAtWar(target_name));
+        }
+        Ok(self.diplomacy.declare_war(player, target))
+    }
+
+    pub fn proposals_of(&self, player: PlayerId) -> impl Iterator<Item = &Proposal> {
+        self.diplomacy
+            .proposals
+            .iter()
+            .filter(move |proposal| proposal.from == player || proposal.to == player)
+    }
+
+    /// Returns every event published by the tick handlers since the last call,
+    /// whoever they were meant for.
+    pub fn drain_events(&mut self) -> Vec<TickResult> {
+        match self.events.lock() {
+            Ok(mut events) => events.drain(..).map(|event| event.result).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    /// Returns the events meant for `player` they haven't collected yet, the
+    /// other players' events stay queued for them.
+    pub fn take_events(&mut self, player: PlayerId) -> Vec<TickResult> {
+        let mut taken = vec![];
+        if let Ok(mut events) = self.events.lock() {
+            events.retain_mut(|event| {
+                if let Some(index) = event.recipients.iter().position(|id| *id == player) {
+                    event.recipients.remove(index);
+                    taken.push(event.result.clone());
+                }
+                !event.recipients.is_empty()
+            });
         }
+        taken
     }
 }
 
 impl Tickable for GameWorld {
-    fn tick(&mut self) -> TickResult {
+    fn tick(&mut self) -> Vec<TickResult> {
         for planet in self.planets.iter_mut() {
-            self.tick_mediator.send(BuildingTickRequest { planet });
+            let bonuses = planet
+                .owner
+                .and_then(|owner| self.players.get(&owner))
+                .map(|player| player.research.bonuses(&self.research))
+                .unwrap_or_default();
+            self.tick_mediator
+                .send(BuildingTickRequest { planet, bonuses });
+            self.tick_mediator.send(PopulationTickRequest { planet });
+            self.tick_mediator.send(ConstructionTickRequest { planet });
+            self.tick_mediator.send(RepairTickRequest {
+                planet,
+                diplomacy: &self.diplomacy,
+            });
         }
-        TickResult::None
+        self.tick_mediator.send(ResearchTickRequest {
+            players: &mut self.players,
+            planets: &mut self.planets,
+            tree: &self.research,
+        });
+        self.tick_mediator.send(FleetMovementTickRequest {
+            planets: &mut self.planets,
+            movements: &mut self.movements,
+            capture_rules: &self.capture_rules,
+            diplomacy: &self.diplomacy,
+        });
+        self.tick_mediator.send(TradeTickRequest {
+            planets: &mut self.planets,
+            routes: &mut self.trade_routes,
+            convoys: &mut self.convoys,
+            movements: &self.movements,
+            diplomacy: &self.diplomacy,
+        });
+        self.tick_mediator.send(MarketTickRequest {
+            planets: &mut self.planets,
+            market: &mut self.market,
+        });
+        self.tick_mediator.send(DiplomacyTickRequest {
+            diplomacy: &mut self.diplomacy,
+            players: &self.players,
+        });
+        while self.tick_mediator.next().is_ok() {}
+        self.assign_fleet_ids();
+        vec![]
     }
 }
 
@@ -33,12 +957,29 @@ mod tests {
     use std::collections::HashMap;
 
     use crate::game::{
-        buildings::{Building, BuildingData, MilitaryBuilding, ResourceBuilding},
+        buildings::{
+            Building, BuildingData, MilitaryBuilding, ResourceBuilding, MAX_BUILDING_LEVEL,
+        },
+        capture::{capture_planet, CaptureRules},
+        catalog::BuildingCatalog,
+        combat::{resolve_battle, BattleOutcome, MAX_BATTLE_ROUNDS},
+        deposit::Deposit,
+        diplomacy::{Diplomacy, Relation, TreatyKind},
+        error::GameError,
+        galaxy::{generate_galaxy, GalaxyConfig, MAX_PLANETS},
         game_world::GameWorld,
-        military::{MilitaryCreation, MilitaryCreationTypes},
+        market::{Market, OrderSide, PricePoint},
+        military::{Fleet, MilitaryCreation, MilitaryCreationTypes},
+        movement::travel_ticks,
         planet::Planet,
+        planet_trait::PlanetTrait,
+        position::Position,
+        research::{research_resource, ResearchTree, TechStatus},
         resource::{Resource, ResourceType},
-        tickable::Tickable,
+        resource_registry::{ResourceDefinition, ResourceRegistry},
+        save::{load_world, migrate, save_world, world_from_string, world_to_string, Migration},
+        siege::{is_hostile, resolve_siege},
+        tickable::{TickResult, Tickable},
     };
 
     fn create_resource_map(
@@ -85,6 +1026,9 @@ mod tests {
                 name: String::from("ResourceBuildingName"),
                 resource_cost,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             create_type,
         };
@@ -94,12 +1038,110 @@ mod tests {
             .is_err());
     }
 
+    #[test]
+    fn building_without_enough_resource_reports_shortfall() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+
+        let resource_to_add = create_resource_map(5, 0, 12);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("ResourceBuildingName"),
+                resource_cost: create_resource_map(20, 0, 10),
+                level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(0, 0, 10),
+        };
+
+        // only credits are short, minerals are covered
+        let mut shortfall = HashMap::new();
+        shortfall.insert(ResourceType::Credits, 15);
+        assert_eq!(
+            planet_1.build_building(Building::ResourceBuilding(resource_building)),
+            Err(GameError::InsufficientResources(shortfall))
+        );
+        // nothing should have been spent
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            5
+        );
+        assert_eq!(
+            planet_1
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            12
+        );
+    }
+
+    #[test]
+    fn add_unknown_resource_error() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.resources.remove(&ResourceType::Food);
+
+        assert_eq!(
+            Planet::add_resource(&mut planet_1.resources, create_resource_map(10, 10, 10)),
+            Err(GameError::UnknownResource(ResourceType::Food))
+        );
+        // the other resources should be left untouched
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            0
+        );
+    }
+
+    #[test]
+    fn building_limit_reached_error() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.building_limit = 1;
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("ResourceBuildingName"),
+                resource_cost: create_resource_map(0, 0, 0),
+                level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(0, 0, 10),
+        };
+
+        assert!(planet_1
+            .build_building(Building::ResourceBuilding(resource_building.clone()))
+            .is_ok());
+        assert_eq!(
+            planet_1.build_building(Building::ResourceBuilding(resource_building)),
+            Err(GameError::BuildingLimitReached(1))
+        );
+    }
+
+    #[test]
+    fn military_to_unknown_fleet_error() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        let ship = MilitaryCreationTypes::Ship(MilitaryCreation::new(
+            String::from("MilitaryCreation1"),
+            1,
+            5,
+            5,
+        ));
+
+        assert_eq!(
+            planet_1.add_military_to_fleet("missing fleet", ship),
+            Err(GameError::UnknownFleet(String::from("missing fleet")))
+        );
+    }
+
     #[test]
     fn building_with_enough_resource_success() {
         let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
 
         let resource_to_add = create_resource_map(20, 0, 0);
-        Planet::add_resource(&mut planet_1.resources, resource_to_add);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
 
         let create_type = create_resource_map(0, 0, 10);
         let resource_cost = create_resource_map(20, 0, 0);
@@ -109,6 +1151,9 @@ mod tests {
                 name: String::from("ResourceBuildingName"),
                 resource_cost,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             create_type,
         };
@@ -123,7 +1168,7 @@ mod tests {
         let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
 
         let resource_to_add = create_resource_map(0, 5, 0);
-        Planet::add_resource(&mut planet_1.resources, resource_to_add);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
 
         let create_type = create_resource_map(0, 0, 10);
         let resource_cost = create_resource_map(0, 5, 0);
@@ -133,6 +1178,9 @@ mod tests {
                 name: String::from("BuildingThatCostsFood"),
                 resource_cost,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             // generate 10 minerals per tick
             create_type,
@@ -188,7 +1236,7 @@ mod tests {
         let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
 
         let resource_to_add = create_resource_map(0, 5, 0);
-        Planet::add_resource(&mut planet_1.resources, resource_to_add);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
 
         let create_type = create_resource_map(5, 0, 10);
         let resource_cost = create_resource_map(0, 5, 0);
@@ -198,6 +1246,9 @@ mod tests {
                 name: String::from("BuildingThatCostsFood"),
                 resource_cost,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             // generate 10 minerals per tick and 5 credits per tick
             create_type,
@@ -275,7 +1326,7 @@ mod tests {
 
         let resource_to_add = create_resource_map(20, 5, 0);
 
-        Planet::add_resource(&mut planet_1.resources, resource_to_add);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
 
         let create_type = create_resource_map(-5, 0, 10);
 
@@ -286,6 +1337,9 @@ mod tests {
                 name: String::from("BuildingThatCostsFood"),
                 resource_cost: resource_costs,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             // generate 10 minerals per tick and 5 credits per tick
             create_type,
@@ -342,7 +1396,7 @@ mod tests {
         let planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
         let mut world = GameWorld::new(vec![planet_1]);
 
-        let mut planet_1 = &mut world.planets[0];
+        let planet_1 = &mut world.planets[0];
 
         let resource_to_add = create_resource_map(1000, 1000, 1000);
 
@@ -350,13 +1404,15 @@ mod tests {
 
         let military_cost_per_tick = create_resource_map(-10, -10, -10);
 
-        Planet::add_resource(&mut planet_1.resources, resource_to_add);
+        Planet::add_resource(&mut planet_1.resources, resource_to_add).unwrap();
 
         let create_type = MilitaryCreation {
             attack: 5,
             defence: 5,
             level: 1,
             name: String::from("MilitaryCreation1"),
+            damage: 0,
+            experience: 0,
         };
 
         let military_building: MilitaryBuilding = MilitaryBuilding {
@@ -364,12 +1420,17 @@ mod tests {
                 name: String::from("MilitaryBuilding"),
                 resource_cost: resource_cost_for_building,
                 level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
             },
             create_type: MilitaryCreationTypes::Ship(create_type),
             military_cost_per_tick,
             progress_required_to_create_military: 20,
             current_progress: 0,
             progress_increase_per_tick: 10,
+            stalled: false,
+            rally_fleet: None,
         };
 
         assert!(planet_1
@@ -393,4 +1454,2401 @@ mod tests {
 
         assert_eq!(fleet.ships.len(), 1);
     }
+
+    #[test]
+    fn upkeep_that_cannot_be_paid_is_refused() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(7, 0, 0)).unwrap();
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("CreditHungryMine"),
+                resource_cost: create_resource_map(0, 0, 0),
+                level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            // costs 5 credits per tick for 10 minerals
+            create_type: create_resource_map(-5, 0, 10),
+        };
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        assert!(world.planets[0]
+            .build_building(Building::ResourceBuilding(resource_building))
+            .is_ok());
+
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            2
+        );
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            10
+        );
+
+        // 2 credits left, the upkeep of 5 can't be paid so nothing is produced
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            2
+        );
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            10
+        );
+    }
+
+    #[test]
+    fn ledger_records_building_debits_and_credits() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(20, 0, 0)).unwrap();
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("Mine"),
+                resource_cost: create_resource_map(10, 0, 0),
+                level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(-5, 0, 10),
+        };
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        assert!(world.planets[0]
+            .build_building(Building::ResourceBuilding(resource_building))
+            .is_ok());
+        world.tick();
+
+        let ledger = &world.planets[0].ledger;
+        let credits: Vec<(u64, i32)> = ledger
+            .entries_for(ResourceType::Credits)
+            .map(|entry| (entry.tick, entry.amount))
+            .collect();
+        // construction cost before the first tick, then the upkeep on tick 1
+        assert_eq!(credits, vec![(0, -10), (1, -5)]);
+        assert_eq!(ledger.entries_from("Mine").count(), 3);
+        assert!(ledger
+            .entries_for(ResourceType::Minerals)
+            .any(|entry| entry.tick == 1 && entry.amount == 10));
+    }
+
+    #[test]
+    fn military_building_stalls_without_upkeep() {
+        let planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        let mut world = GameWorld::new(vec![planet_1]);
+
+        // enough credits for exactly two ticks of upkeep
+        Planet::add_resource(
+            &mut world.planets[0].resources,
+            create_resource_map(20, 0, 0),
+        )
+        .unwrap();
+
+        let military_building = MilitaryBuilding::new(
+            String::from("Shipyard"),
+            create_resource_map(0, 0, 0),
+            MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                String::from("MilitaryCreation1"),
+                1,
+                5,
+                5,
+            )),
+            create_resource_map(-10, 0, 0),
+            30,
+            10,
+        );
+        assert!(world.planets[0]
+            .build_building(Building::MilitaryBuilding(military_building))
+            .is_ok());
+
+        world.tick();
+        world.tick();
+        assert!(world.drain_events().is_empty());
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            0
+        );
+
+        // the third tick would complete the ship but can't be paid for
+        world.tick();
+        let fleet_name = world.planets[0].default_fleet_name();
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 0);
+
+        let mut shortfall = HashMap::new();
+        shortfall.insert(ResourceType::Credits, 10);
+        match &world.drain_events()[..] {
+            [TickResult::MilitaryStalled(name, missing)] => {
+                assert_eq!(name, "Shipyard on planet_1");
+                assert_eq!(missing, &shortfall);
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        match &world.planets[0].buildings[0] {
+            Building::MilitaryBuilding(mil) => {
+                assert!(mil.stalled);
+                assert_eq!(mil.current_progress, 20);
+            }
+            _ => panic!("expected a military building"),
+        }
+
+        // once the upkeep can be paid again the ship completes, paying for that tick too
+        Planet::add_resource(
+            &mut world.planets[0].resources,
+            create_resource_map(10, 0, 0),
+        )
+        .unwrap();
+        world.tick();
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 1);
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            0
+        );
+    }
+
+    #[test]
+    fn refinery_consumes_and_produces_in_one_tick() {
+        let planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        let mut world = GameWorld::new(vec![planet_1]);
+
+        Planet::add_resource(
+            &mut world.planets[0].resources,
+            create_resource_map(0, 0, 15),
+        )
+        .unwrap();
+
+        // turns 10 minerals into 5 credits and a ship every tick
+        let refinery = MilitaryBuilding::new(
+            String::from("Refinery"),
+            create_resource_map(0, 0, 0),
+            MilitaryCreationTypes::Ship(MilitaryCreation::new(String::from("Freighter"), 1, 0, 2)),
+            create_resource_map(5, 0, -10),
+            10,
+            10,
+        );
+        assert!(world.planets[0]
+            .build_building(Building::MilitaryBuilding(refinery))
+            .is_ok());
+
+        world.tick();
+        let fleet_name = world.planets[0].default_fleet_name();
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 1);
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            5
+        );
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            5
+        );
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "Refinery on planet_1 finished building Freighter")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        // not enough minerals left, nothing is consumed or produced
+        world.tick();
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 1);
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            5
+        );
+    }
+
+    #[test]
+    fn refinery_output_is_capped_by_storage() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.base_storage = 12;
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(10, 0, 12)).unwrap();
+        let refinery = MilitaryBuilding::new(
+            String::from("Refinery"),
+            create_resource_map(0, 0, 0),
+            MilitaryCreationTypes::Ship(MilitaryCreation::new(String::from("Freighter"), 1, 0, 2)),
+            create_resource_map(5, 0, -10),
+            10,
+            10,
+        );
+        planet_1
+            .buildings
+            .push(Building::MilitaryBuilding(refinery));
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        let planet = &world.planets[0];
+        assert_eq!(
+            planet.get_resource(ResourceType::Credits).unwrap().amount,
+            12
+        );
+        assert_eq!(
+            planet.get_resource(ResourceType::Minerals).unwrap().amount,
+            2
+        );
+        assert_eq!(
+            notifications(&mut world),
+            vec![
+                "Refinery on planet_1 finished building Freighter",
+                "Storage on planet_1 is full, 3 Credits went to waste",
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrading_building_charges_scaled_cost_and_scales_output() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(25, 0, 0)).unwrap();
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("Mine"),
+                resource_cost: create_resource_map(10, 0, 0),
+                level: 1,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(0, 0, 10),
+        };
+        assert!(planet_1
+            .build_building(Building::ResourceBuilding(resource_building))
+            .is_ok());
+
+        // level 2 costs twice the building cost, we only have 15 credits left
+        let mut shortfall = HashMap::new();
+        shortfall.insert(ResourceType::Credits, 5);
+        assert_eq!(
+            planet_1.upgrade_building(0),
+            Err(GameError::InsufficientResources(shortfall))
+        );
+        assert_eq!(
+            planet_1.upgrade_building(1),
+            Err(GameError::UnknownBuilding(1))
+        );
+
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(5, 0, 0)).unwrap();
+        assert!(planet_1.upgrade_building(0).is_ok());
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            0
+        );
+        assert_eq!(planet_1.buildings[0].get_building_data().level, 2);
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            20
+        );
+    }
+
+    #[test]
+    fn upgraded_military_building_progresses_faster() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+
+        let mut military_building = MilitaryBuilding::new(
+            String::from("Shipyard"),
+            create_resource_map(0, 0, 0),
+            MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                String::from("MilitaryCreation1"),
+                1,
+                5,
+                5,
+            )),
+            create_resource_map(0, 0, 0),
+            20,
+            10,
+        );
+        military_building.building.level = 2;
+        assert!(planet_1
+            .build_building(Building::MilitaryBuilding(military_building))
+            .is_ok());
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+
+        let fleet_name = world.planets[0].default_fleet_name();
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 1);
+    }
+
+    #[test]
+    fn upgrading_past_max_level_error() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+
+        let resource_building: ResourceBuilding = ResourceBuilding {
+            building: BuildingData {
+                name: String::from("Mine"),
+                resource_cost: create_resource_map(0, 0, 0),
+                level: MAX_BUILDING_LEVEL,
+                build_time: 0,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(0, 0, 10),
+        };
+        assert!(planet_1
+            .build_building(Building::ResourceBuilding(resource_building))
+            .is_ok());
+        assert_eq!(
+            planet_1.upgrade_building(0),
+            Err(GameError::MaxLevelReached(
+                String::from("Mine"),
+                MAX_BUILDING_LEVEL
+            ))
+        );
+    }
+
+    fn timed_mine(name: &str, build_time: u32) -> Building {
+        Building::ResourceBuilding(ResourceBuilding {
+            building: BuildingData {
+                name: String::from(name),
+                resource_cost: create_resource_map(10, 0, 0),
+                level: 1,
+                build_time,
+                workers: 0,
+                catalog_id: None,
+            },
+            create_type: create_resource_map(0, 0, 10),
+        })
+    }
+
+    #[test]
+    fn queued_building_completes_after_build_time() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(10, 0, 0)).unwrap();
+
+        assert!(planet_1.build_building(timed_mine("Mine", 2)).is_ok());
+        // paid up front but not built yet
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            0
+        );
+        assert!(planet_1.buildings.is_empty());
+        assert_eq!(
+            planet_1.construction_queue.status(),
+            vec!["Mine (0/2 ticks)"]
+        );
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        assert_eq!(
+            world.planets[0].construction_queue.status(),
+            vec!["Mine (1/2 ticks)"]
+        );
+        assert!(world.drain_events().is_empty());
+
+        world.tick();
+        assert!(world.planets[0].construction_queue.is_empty());
+        assert_eq!(world.planets[0].buildings.len(), 1);
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "Mine has been built on planet_1")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        // the finished building starts producing on the next tick
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            0
+        );
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            10
+        );
+    }
+
+    #[test]
+    fn construction_queue_reorder_and_cancel() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(30, 0, 0)).unwrap();
+        planet_1.building_limit = 3;
+
+        assert!(planet_1.build_building(timed_mine("First", 3)).is_ok());
+        assert!(planet_1.build_building(timed_mine("Second", 3)).is_ok());
+        assert!(planet_1.build_building(timed_mine("Third", 3)).is_ok());
+        // queued buildings count towards the limit
+        assert_eq!(
+            planet_1.build_building(timed_mine("Fourth", 0)),
+            Err(GameError::BuildingLimitReached(3))
+        );
+
+        assert!(planet_1.reorder_construction(2, 0).is_ok());
+        assert_eq!(
+            planet_1.reorder_construction(0, 3),
+            Err(GameError::UnknownConstruction(3))
+        );
+        assert_eq!(
+            planet_1.construction_queue.status(),
+            vec![
+                "Third (0/3 ticks)",
+                "First (0/3 ticks)",
+                "Second (0/3 ticks)"
+            ]
+        );
+
+        // cancelling gives back half of the 10 credits
+        assert!(planet_1.cancel_construction(1).is_ok());
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            5
+        );
+        assert_eq!(
+            planet_1.cancel_construction(2),
+            Err(GameError::UnknownConstruction(2))
+        );
+        assert_eq!(
+            planet_1.construction_queue.status(),
+            vec!["Third (0/3 ticks)", "Second (0/3 ticks)"]
+        );
+    }
+
+    #[test]
+    fn prerequisites_survive_renamed_buildings() {
+        let mut catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(20, 0, 0)).unwrap();
+        assert!(planet_1.build_from_catalog(&catalog, "colony_hub").is_ok());
+
+        // a new display name in the catalog doesn't undo the hub already standing
+        for template in catalog.buildings.iter_mut() {
+            if template.id == "colony_hub" {
+                template.name = String::from("Settlement");
+            }
+        }
+        assert!(planet_1
+            .build_from_catalog(&catalog, "mineral_mine")
+            .is_ok());
+        assert_eq!(
+            planet_1.buildings[0].get_building_data().catalog_id,
+            Some(String::from("colony_hub"))
+        );
+    }
+
+    #[test]
+    fn build_from_toml_catalog() {
+        let catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(20, 0, 0)).unwrap();
+
+        assert_eq!(
+            planet_1.build_from_catalog(&catalog, "mineral_mine"),
+            Err(GameError::MissingPrerequisite(String::from("Colony Hub")))
+        );
+        assert_eq!(
+            planet_1.build_from_catalog(&catalog, "space_elevator"),
+            Err(GameError::UnknownTemplate(String::from("space_elevator")))
+        );
+
+        assert!(planet_1.build_from_catalog(&catalog, "colony_hub").is_ok());
+        assert!(planet_1
+            .build_from_catalog(&catalog, "mineral_mine")
+            .is_ok());
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            0
+        );
+        assert_eq!(
+            planet_1.construction_queue.status(),
+            vec!["Mineral Mine (0/3 ticks)"]
+        );
+
+        match &planet_1.buildings[0] {
+            Building::ResourceBuilding(res) => {
+                assert_eq!(res.create_type[&ResourceType::Credits].amount, 5);
+                assert_eq!(res.create_type[&ResourceType::Food].amount, 2);
+            }
+            _ => panic!("expected a resource building"),
+        }
+
+        match catalog.get("shipyard").unwrap().create() {
+            Building::MilitaryBuilding(mil) => {
+                assert_eq!(mil.building.build_time, 5);
+                assert_eq!(
+                    mil.military_cost_per_tick[&ResourceType::Minerals].amount,
+                    -3
+                );
+                assert_eq!(mil.progress_increase_per_tick, 5);
+                assert_eq!(mil.create_type.get_creation().name, "Corvette");
+            }
+            _ => panic!("expected a military building"),
+        }
+    }
+
+    #[test]
+    fn build_from_json_catalog() {
+        let catalog = BuildingCatalog::from_json_str(
+            r#"{
+                "buildings": [
+                    {
+                        "id": "outpost",
+                        "name": "Outpost",
+                        "cost": { "Minerals": 5 },
+                        "military": {
+                            "kind": "defence_turret",
+                            "name": "Laser Turret",
+                            "attack": 3,
+                            "defence": 6,
+                            "progress_required": 10,
+                            "progress_per_tick": 10
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(0, 0, 5)).unwrap();
+        assert!(planet_1.build_from_catalog(&catalog, "outpost").is_ok());
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        // turrets are planetary defences, not part of the fleet
+        let fleet_name = world.planets[0].default_fleet_name();
+        assert!(world.planets[0].military[&fleet_name].ships.is_empty());
+        match &world.planets[0].defences[..] {
+            [turret] => assert_eq!(turret.name, "Laser Turret"),
+            defences => panic!("unexpected defences {:?}", defences),
+        }
+        assert_eq!(world.planets[0].defence_strength(), 6);
+    }
+
+    #[test]
+    fn invalid_catalog_error() {
+        assert!(matches!(
+            BuildingCatalog::from_toml_str(
+                r#"
+                [[buildings]]
+                id = "mine"
+                name = "Mine"
+                prerequisites = ["hub"]
+                "#
+            ),
+            Err(GameError::InvalidCatalog(_))
+        ));
+        // a typo isn't taken for a new resource type
+        assert!(matches!(
+            BuildingCatalog::from_toml_str(
+                r#"
+                [[buildings]]
+                id = "mine"
+                name = "Mine"
+                produces = { Minerlas = 1 }
+                "#
+            ),
+            Err(GameError::InvalidCatalog(_))
+        ));
+        assert_eq!(ResourceType::known("Minerlas"), None);
+
+        // a type another config registered, but this world doesn't have
+        ResourceType::from_id("Unobtainium");
+        let catalog = BuildingCatalog::from_toml_str(
+            r#"
+            [[buildings]]
+            id = "mine"
+            name = "Mine"
+            produces = { Unobtainium = 1 }
+            "#,
+        )
+        .unwrap();
+        let mut world = GameWorld::new(vec![]);
+        assert_eq!(
+            world.set_catalog(catalog.clone()),
+            Err(GameError::InvalidCatalog(String::from(
+                "building \"mine\" uses unknown resource \"Unobtainium\""
+            )))
+        );
+
+        let mut registry = ResourceRegistry::default();
+        registry.resources.push(ResourceDefinition {
+            id: String::from("Unobtainium"),
+            name: String::from("Unobtainium"),
+            emoji: String::new(),
+        });
+        world.set_resources(registry);
+        assert_eq!(world.set_catalog(catalog), Ok(()));
+    }
+
+    #[test]
+    fn configured_resource_types() {
+        let registry = ResourceRegistry::from_toml_str(
+            r#"
+            [[resources]]
+            id = "energy"
+            name = "Energy"
+            emoji = "⚡"
+            "#,
+        )
+        .unwrap();
+        let energy = ResourceType::from_id("energy");
+        assert_eq!(registry.label(energy), "⚡ Energy");
+        assert_eq!(registry.label(ResourceType::Credits), "Credits");
+
+        let mut world = GameWorld::new(vec![Planet::new(String::from("planet_1"), 0, 0)]);
+        world.set_resources(registry);
+        let planet = &mut world.planets[0];
+        assert_eq!(planet.get_resource(energy).unwrap().amount, 0);
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 0);
+
+        let mut energy_map = HashMap::new();
+        energy_map.insert(
+            energy,
+            Resource {
+                resource_type: energy,
+                amount: 7,
+            },
+        );
+        assert_eq!(
+            Planet::add_resource(&mut planet.resources, energy_map),
+            Ok(())
+        );
+
+        // custom types survive a save as their id
+        let saved = world_to_string(&world).unwrap();
+        let loaded = world_from_string(&saved).unwrap();
+        assert_eq!(loaded.planets[0].get_resource(energy).unwrap().amount, 7);
+
+        // types the save's registry doesn't define are rejected
+        let tampered = saved.replace(
+            "\"resource_type\":\"energy\"",
+            "\"resource_type\":\"enregy\"",
+        );
+        assert_ne!(tampered, saved);
+        assert!(matches!(
+            world_from_string(&tampered),
+            Err(GameError::InvalidSave(_))
+        ));
+        assert_eq!(ResourceType::known("enregy"), None);
+
+        assert!(matches!(
+            ResourceRegistry::from_toml_str(
+                r#"
+                [[resources]]
+                id = "dark matter"
+                name = "Dark Matter"
+                "#
+            ),
+            Err(GameError::InvalidResourceConfig(_))
+        ));
+    }
+
+    #[test]
+    fn save_and_load_world_round_trip() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 3, 4);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(100, 0, 0)).unwrap();
+
+        let military_building = MilitaryBuilding::new(
+            String::from("Shipyard"),
+            create_resource_map(10, 0, 0),
+            MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                String::from("MilitaryCreation1"),
+                1,
+                5,
+                5,
+            )),
+            create_resource_map(-1, 0, 0),
+            30,
+            10,
+        );
+        assert!(planet_1
+            .build_building(Building::MilitaryBuilding(military_building))
+            .is_ok());
+        assert!(planet_1.build_building(timed_mine("Mine", 4)).is_ok());
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        world.register_player(111, String::from("alice")).unwrap();
+        world.claim_planet(111, "planet_1").unwrap();
+        world.tick();
+
+        let path = std::env::temp_dir().join("space_game_discord_round_trip.json");
+        save_world(&world, &path).unwrap();
+        let mut loaded = load_world(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let planet = &loaded.planets[0];
+        assert_eq!(planet.name, "planet_1");
+        assert_eq!((planet.position.x, planet.position.y), (3, 4));
+        assert_eq!(
+            planet.get_resource(ResourceType::Credits).unwrap().amount,
+            79
+        );
+        assert_eq!(planet.construction_queue.status(), vec!["Mine (1/4 ticks)"]);
+        assert_eq!(
+            planet.ledger.entries.len(),
+            world.planets[0].ledger.entries.len()
+        );
+        match &planet.buildings[0] {
+            Building::MilitaryBuilding(mil) => assert_eq!(mil.current_progress, 10),
+            _ => panic!("expected a military building"),
+        }
+        assert!(loaded.catalog.get("shipyard").is_some());
+        assert_eq!(loaded.get_player(111).unwrap().name, "alice");
+        assert_eq!(planet.owner, Some(111));
+
+        // the loaded world keeps ticking where the saved one stopped
+        loaded.tick();
+        loaded.tick();
+        let fleet_name = loaded.planets[0].default_fleet_name();
+        assert_eq!(loaded.planets[0].military[&fleet_name].ships.len(), 1);
+    }
+
+    #[test]
+    fn save_migrations_are_applied_in_order() {
+        fn rename_planets(mut save: serde_json::Value) -> Result<serde_json::Value, GameError> {
+            if let Some(save) = save.as_object_mut() {
+                let planets = save.remove("worlds").unwrap_or_default();
+                save.insert(String::from("planets"), planets);
+            }
+            Ok(save)
+        }
+        fn default_catalog(mut save: serde_json::Value) -> Result<serde_json::Value, GameError> {
+            save["catalog"] = serde_json::json!({ "buildings": [] });
+            Ok(save)
+        }
+        let migrations: &[Migration] = &[rename_planets, default_catalog];
+
+        let migrated = migrate(
+            serde_json::json!({ "version": 1, "worlds": [] }),
+            migrations,
+        )
+        .unwrap();
+        assert_eq!(
+            migrated,
+            serde_json::json!({ "version": 3, "planets": [], "catalog": { "buildings": [] } })
+        );
+
+        // already up to date saves are left alone
+        let migrated = migrate(serde_json::json!({ "version": 3 }), migrations).unwrap();
+        assert_eq!(migrated, serde_json::json!({ "version": 3 }));
+    }
+
+    #[test]
+    fn loading_unsupported_save_error() {
+        let world = GameWorld::new(vec![Planet::new(String::from("planet_1"), 0, 0)]);
+        let save = world_to_string(&world).unwrap();
+        assert!(world_from_string(&save).is_ok());
+
+        let future_save = save.replace("\"version\":1", "\"version\":99");
+        assert!(matches!(
+            world_from_string(&future_save),
+            Err(GameError::UnsupportedSaveVersion(99))
+        ));
+        assert!(matches!(
+            world_from_string("{}"),
+            Err(GameError::InvalidSave(_))
+        ));
+    }
+
+    #[test]
+    fn players_can_only_act_on_what_they_own() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 10, 10),
+        ]);
+        world.catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+
+        let alice = 111;
+        let bob = 222;
+        assert_eq!(
+            world.claim_planet(alice, "planet_1"),
+            Err(GameError::UnknownPlayer(alice))
+        );
+        world.register_player(alice, String::from("alice")).unwrap();
+        world.register_player(bob, String::from("bob")).unwrap();
+        assert_eq!(
+            world.register_player(bob, String::from("bob")),
+            Err(GameError::PlayerAlreadyRegistered(bob))
+        );
+
+        world.claim_planet(alice, "planet_1").unwrap();
+        world.claim_planet(bob, "planet_2").unwrap();
+        assert_eq!(
+            world.claim_planet(bob, "planet_1"),
+            Err(GameError::PlanetAlreadyOwned(String::from("planet_1")))
+        );
+        assert_eq!(
+            world.claim_planet(bob, "planet_3"),
+            Err(GameError::UnknownPlanet(String::from("planet_3")))
+        );
+        assert_eq!(
+            world
+                .planets_of(alice)
+                .map(|planet| planet.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["planet_1"]
+        );
+
+        assert!(world
+            .build_from_catalog(alice, "planet_1", "colony_hub")
+            .is_ok());
+        assert_eq!(
+            world.build_from_catalog(bob, "planet_1", "colony_hub"),
+            Err(GameError::NotOwner(String::from("planet_1")))
+        );
+        assert_eq!(
+            world.build_building(bob, "planet_1", timed_mine("Mine", 0)),
+            Err(GameError::NotOwner(String::from("planet_1")))
+        );
+        assert_eq!(world.get_planet("planet_1").unwrap().buildings.len(), 1);
+
+        // the default fleet follows the planet's owner
+        let fleet_name = world.get_planet("planet_1").unwrap().default_fleet_name();
+        assert!(world
+            .owned_fleet_mut(alice, "planet_1", &fleet_name)
+            .is_ok());
+        assert!(matches!(
+            world.owned_fleet_mut(bob, "planet_1", &fleet_name),
+            Err(GameError::NotOwner(_))
+        ));
+
+        assert!(world
+            .create_fleet(bob, "planet_2", String::from("Raiders"))
+            .is_ok());
+        assert_eq!(
+            world.create_fleet(bob, "planet_2", String::from("Raiders")),
+            Err(GameError::FleetAlreadyExists(String::from("Raiders")))
+        );
+        assert_eq!(
+            world.create_fleet(alice, "planet_2", String::from("Defenders")),
+            Err(GameError::NotOwner(String::from("planet_2")))
+        );
+        assert_eq!(
+            world
+                .owned_fleet_mut(bob, "planet_2", "Raiders")
+                .unwrap()
+                .owner,
+            Some(bob)
+        );
+    }
+
+    fn create_fleet_of(units: &[(&str, u32, u32, u32)]) -> Fleet {
+        let mut fleet = Fleet::new(None);
+        for (name, count, attack, defence) in units {
+            for _ in 0..*count {
+                fleet
+                    .ships
+                    .push(MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                        String::from(*name),
+                        1,
+                        *attack,
+                        *defence,
+                    )));
+            }
+        }
+        fleet
+    }
+
+    #[test]
+    fn fleet_battle_attacker_wins() {
+        let mut attacker = create_fleet_of(&[("Corvette", 3, 5, 5)]);
+        let mut defender = create_fleet_of(&[("Drone", 2, 2, 4), ("Frigate", 1, 3, 10)]);
+
+        let report = resolve_battle("Raiders", &mut attacker, "Home Guard", &mut defender);
+
+        // round 1: 15 damage destroys both drones and wounds the frigate down to 3 hp
+        // the defenders deal 7 damage which destroys one corvette and wounds the next
+        // round 2: the frigate falls and takes the wounded corvette with it
+        assert_eq!(report.outcome, BattleOutcome::AttackerWon);
+        assert_eq!(report.rounds.len(), 2);
+        assert_eq!(report.rounds[0].attacker_damage, 15);
+        assert_eq!(report.rounds[0].defender_damage, 7);
+        assert_eq!(report.rounds[0].attacker_losses, vec!["Corvette"]);
+        assert_eq!(report.rounds[1].attacker_losses, vec!["Corvette"]);
+        assert_eq!(report.defender_losses["Drone"], 2);
+        assert_eq!(report.defender_losses["Frigate"], 1);
+        assert_eq!(report.attacker_losses["Corvette"], 2);
+        assert!(defender.ships.is_empty());
+
+        // the survivor earned 35 experience for the battle and its 3 kills
+        assert_eq!(report.attacker_promotions["Corvette"], 1);
+        let veteran = attacker.ships[0].get_creation();
+        assert_eq!(attacker.ships.len(), 1);
+        assert_eq!(
+            (
+                veteran.level,
+                veteran.attack,
+                veteran.defence,
+                veteran.experience
+            ),
+            (2, 6, 6, 5)
+        );
+        assert_eq!(veteran.hp(), 6);
+    }
+
+    #[test]
+    fn fleet_battle_stalemate_is_a_draw() {
+        // unarmed fleets stop after the first round
+        let mut attacker = create_fleet_of(&[("Freighter", 2, 0, 10)]);
+        let mut defender = create_fleet_of(&[("Bunker", 1, 0, 10)]);
+        let report = resolve_battle("Freighters", &mut attacker, "Bunkers", &mut defender);
+        assert_eq!(report.outcome, BattleOutcome::Draw);
+        assert_eq!(report.rounds.len(), 1);
+        assert!(report.attacker_losses.is_empty() && report.defender_losses.is_empty());
+
+        // wounds carry over between rounds until the round limit ends the battle
+        let mut attacker = create_fleet_of(&[("Scout", 2, 1, 30)]);
+        let mut defender = create_fleet_of(&[("Bunker", 1, 1, 30)]);
+        let report = resolve_battle("Scouts", &mut attacker, "Bunkers", &mut defender);
+        assert_eq!(report.outcome, BattleOutcome::Draw);
+        assert_eq!(report.rounds.len() as u32, MAX_BATTLE_ROUNDS);
+        assert!(report.attacker_losses.is_empty() && report.defender_losses.is_empty());
+        assert_eq!(defender.ships[0].get_creation().hp(), 10);
+        assert_eq!(attacker.ships[0].get_creation().hp(), 20);
+        assert_eq!(attacker.ships[1].get_creation().hp(), 30);
+    }
+
+    fn turret(attack: u32, defence: u32) -> MilitaryCreation {
+        MilitaryCreation::new(String::from("Flak Turret"), 1, attack, defence)
+    }
+
+    #[test]
+    fn turrets_cannot_join_fleets() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        let fleet_name = planet_1.default_fleet_name();
+
+        assert_eq!(
+            planet_1.add_military_to_fleet(
+                &fleet_name,
+                MilitaryCreationTypes::DefenceTurret(turret(2, 8))
+            ),
+            Err(GameError::ImmobileUnit(String::from("Flak Turret")))
+        );
+        assert!(planet_1
+            .add_military_unit(MilitaryCreationTypes::DefenceTurret(turret(2, 8)))
+            .is_ok());
+        assert_eq!(planet_1.defences.len(), 1);
+        assert!(planet_1.military[&fleet_name].ships.is_empty());
+    }
+
+    #[test]
+    fn siege_breaks_fleets_and_turrets() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.set_owner(Some(1));
+        let fleet_name = planet_1.default_fleet_name();
+        planet_1
+            .add_military_to_fleet(
+                &fleet_name,
+                MilitaryCreationTypes::Ship(MilitaryCreation::new(String::from("Drone"), 1, 2, 4)),
+            )
+            .unwrap();
+        planet_1.defences = vec![turret(2, 8), turret(2, 8)];
+
+        let mut attacker = create_fleet_of(&[("Corvette", 4, 5, 5)]);
+        attacker.owner = Some(2);
+        assert!(is_hostile(&planet_1, &attacker, &Diplomacy::default()));
+
+        let report = resolve_siege(&mut planet_1, "Raiders", &mut attacker);
+        // the drone falls first and wounds a corvette, then 20 damage is enough for
+        // both turrets while they finish off the wounded corvette
+        assert_eq!(report.battles.len(), 2);
+        assert_eq!(report.battles[0].defender, "planet_1 fleet");
+        assert_eq!(report.battles[1].defender, "planet_1 defences");
+        assert_eq!(report.battles[1].defender_losses["Flak Turret"], 2);
+        assert!(planet_1.defences.is_empty());
+        assert_eq!(report.planet_defence, 0);
+        // the 3 survivors were promoted to an attack of 6
+        assert_eq!(report.siege_strength, 18);
+        assert!(report.can_capture);
+    }
+
+    #[test]
+    fn surviving_turrets_prevent_capture() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.set_owner(Some(1));
+        planet_1.defences = vec![turret(0, 100)];
+
+        let mut attacker = create_fleet_of(&[("Corvette", 1, 5, 5)]);
+        attacker.owner = Some(2);
+
+        // ten rounds of 5 damage only wound the turret
+        let report = resolve_siege(&mut planet_1, "Raiders", &mut attacker);
+        assert_eq!(report.battles.len(), 1);
+        assert_eq!(planet_1.defences.len(), 1);
+        assert_eq!(planet_1.defences[0].hp(), 50);
+        assert_eq!(report.planet_defence, 50);
+        assert_eq!(report.siege_strength, 5);
+        assert!(!report.can_capture);
+
+        // a badly wounded turret survives the next siege but can't hold the planet
+        planet_1.defences[0].damage = 48;
+        let mut attacker = create_fleet_of(&[("Corvette", 1, 5, 5)]);
+        attacker.owner = Some(2);
+        let report = resolve_siege(&mut planet_1, "Raiders", &mut attacker);
+        assert_eq!(planet_1.defences[0].hp(), 2);
+        assert_eq!(report.planet_defence, 2);
+        assert!(report.can_capture);
+
+        // the planet's own fleets are never hostile
+        let mut own_fleet = create_fleet_of(&[("Corvette", 1, 5, 5)]);
+        own_fleet.owner = Some(1);
+        assert!(!is_hostile(&planet_1, &own_fleet, &Diplomacy::default()));
+    }
+
+    #[test]
+    fn travel_time_depends_on_distance_and_speed() {
+        let from = Position { x: 0, y: 0 };
+        assert_eq!(travel_ticks(&from, &Position { x: 30, y: 40 }, 5), 10);
+        assert_eq!(travel_ticks(&from, &Position { x: 30, y: 40 }, 7), 8);
+        assert_eq!(travel_ticks(&from, &Position { x: 1, y: 0 }, 5), 1);
+        assert_eq!(travel_ticks(&from, &from, 5), 1);
+    }
+
+    #[test]
+    fn fleet_moves_across_the_map() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 30, 40),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(1, "planet_2").unwrap();
+        world
+            .create_fleet(1, "planet_1", String::from("Scouts"))
+            .unwrap();
+
+        assert_eq!(
+            world.move_fleet(1, "planet_1", "Scouts", "planet_2"),
+            Err(GameError::EmptyFleet(String::from("Scouts")))
+        );
+        world
+            .owned_fleet_mut(1, "planet_1", "Scouts")
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Scout", 2, 1, 1)]).ships);
+        assert_eq!(
+            world.move_fleet(1, "planet_1", "Scouts", "planet_1"),
+            Err(GameError::AlreadyThere(String::from("planet_1")))
+        );
+        assert_eq!(
+            world.move_fleet(2, "planet_1", "Scouts", "planet_2"),
+            Err(GameError::UnknownPlayer(2))
+        );
+
+        // 50 units of distance at the default speed of 5
+        assert_eq!(
+            world.move_fleet(1, "planet_1", "Scouts", "planet_2"),
+            Ok(10)
+        );
+        assert!(!world.planets[0].military.contains_key("Scouts"));
+
+        for _ in 0..5 {
+            world.tick();
+        }
+        assert_eq!(world.movements[0].fleet.position, Position { x: 15, y: 20 });
+        assert_eq!(world.movements[0].ticks_remaining(), 5);
+        assert!(world.drain_events().is_empty());
+
+        for _ in 0..5 {
+            world.tick();
+        }
+        assert!(world.movements.is_empty());
+        let fleet = &world.planets[1].military["Scouts"];
+        assert_eq!(fleet.position, Position { x: 30, y: 40 });
+        assert_eq!(fleet.ships.len(), 2);
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "Scouts arrived at planet_2")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+
+    #[test]
+    fn fleet_leaves_from_its_planet_position() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 30, 40),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(1, "planet_2").unwrap();
+        // a fleet that was never given a position
+        let mut fleet = create_fleet_of(&[("Scout", 1, 1, 1)]);
+        fleet.owner = Some(1);
+        world.planets[1]
+            .military
+            .insert(String::from("Scouts"), fleet);
+
+        assert_eq!(
+            world.move_fleet(1, "planet_2", "Scouts", "planet_1"),
+            Ok(10)
+        );
+        assert_eq!(world.movements[0].fleet.position, Position { x: 30, y: 40 });
+        world.tick();
+        assert_eq!(world.movements[0].fleet.position, Position { x: 27, y: 36 });
+    }
+
+    #[test]
+    fn hostile_fleet_arrival_starts_a_siege() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        world.planets[1].defences = vec![turret(1, 8)];
+
+        let fleet_name = world.planets[0].default_fleet_name();
+        world
+            .owned_fleet_mut(1, "planet_1", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 3, 5, 5)]).ships);
+        assert_eq!(
+            world.move_fleet(1, "planet_1", &fleet_name, "planet_2"),
+            Ok(1)
+        );
+
+        world.tick();
+        let events = world.drain_events();
+        match &events[..] {
+            [TickResult::Siege(report), TickResult::Captured(capture), TickResult::Notification(message)] =>
+            {
+                assert_eq!(report.planet, "planet_2");
+                assert_eq!(report.battles.len(), 1);
+                assert!(report.can_capture);
+                assert_eq!(capture.previous_owner, Some(2));
+                assert_eq!(capture.new_owner, Some(1));
+                assert_eq!(message, "planet_1 fleet arrived at planet_2");
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert!(world.planets[1].defences.is_empty());
+        assert_eq!(world.planets[1].owner, Some(1));
+        assert_eq!(world.planets[1].military[&fleet_name].owner, Some(1));
+    }
+
+    #[test]
+    fn repelled_fleet_retreats_to_its_origin() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        world.planets[1].defences = vec![turret(0, 100)];
+
+        let fleet_name = world.planets[0].default_fleet_name();
+        world
+            .owned_fleet_mut(1, "planet_1", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 1, 5, 5)]).ships);
+        world
+            .move_fleet(1, "planet_1", &fleet_name, "planet_2")
+            .unwrap();
+
+        world.tick();
+        match &world.drain_events()[..] {
+            [TickResult::Siege(report), TickResult::Notification(message)] => {
+                assert!(!report.can_capture);
+                assert_eq!(message, "planet_1 fleet retreats from planet_2 to planet_1");
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert_eq!(world.planets[1].owner, Some(2));
+        assert!(world.planets[1]
+            .military
+            .values()
+            .all(|fleet| fleet.owner == Some(2)));
+        assert_eq!(world.movements.len(), 1);
+        assert_eq!(world.movements[0].destination, "planet_1");
+
+        world.tick();
+        assert!(world.movements.is_empty());
+        assert_eq!(world.planets[0].military[&fleet_name].ships.len(), 1);
+        assert_eq!(
+            notifications(&mut world),
+            vec![String::from("planet_1 fleet arrived at planet_1")]
+        );
+    }
+
+    #[test]
+    fn captured_planet_is_looted_and_damaged() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+
+        let target = &mut world.planets[1];
+        Planet::add_resource(&mut target.resources, create_resource_map(110, 0, 0)).unwrap();
+        let mut mine = timed_mine("Mine", 0);
+        mine.get_building_data_mut().level = 4;
+        target.buildings.push(mine);
+        target.buildings.push(timed_mine("Mine", 0));
+        assert!(target.build_building(timed_mine("Mine", 3)).is_ok());
+
+        let fleet_name = world.planets[0].default_fleet_name();
+        world
+            .owned_fleet_mut(1, "planet_1", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 3, 5, 5)]).ships);
+        world
+            .move_fleet(1, "planet_1", &fleet_name, "planet_2")
+            .unwrap();
+        world.tick();
+        match world
+            .drain_events()
+            .iter()
+            .find(|event| matches!(event, TickResult::Captured(_)))
+        {
+            Some(TickResult::Captured(report)) => {
+                assert_eq!(report.buildings_damaged, 2);
+                assert_eq!(report.constructions_cancelled, 1);
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        let target = &world.planets[1];
+        assert_eq!(
+            target.get_resource(ResourceType::Credits).unwrap().amount,
+            50
+        );
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            50
+        );
+        assert_eq!(
+            world.planets[0]
+                .ledger
+                .entries_for(ResourceType::Credits)
+                .filter(|entry| entry.source == "Loot from planet_2")
+                .map(|entry| entry.amount)
+                .collect::<Vec<_>>(),
+            vec![50]
+        );
+        let levels: Vec<u32> = target
+            .buildings
+            .iter()
+            .map(|building| building.get_building_data().level)
+            .collect();
+        assert_eq!(levels, vec![2, 1]);
+        assert!(target.construction_queue.is_empty());
+
+        // bob's emptied default fleet is replaced by one for alice
+        let default_fleet = target.default_fleet_name();
+        assert_eq!(target.military[&default_fleet].owner, Some(1));
+        assert!(target.military.values().all(|fleet| fleet.owner == Some(1)));
+    }
+
+    #[test]
+    fn large_stockpiles_are_looted_without_overflow() {
+        let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.set_owner(Some(1));
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(i32::MAX, 0, 0)).unwrap();
+
+        let report = capture_planet(&mut planet_1, Some(2), &CaptureRules::default());
+        assert_eq!(report.loot[&ResourceType::Credits], i32::MAX / 2);
+        assert_eq!(
+            planet_1.get_resource(ResourceType::Credits).unwrap().amount,
+            i32::MAX - i32::MAX / 2
+        );
+    }
+
+    #[test]
+    fn loot_stays_on_the_captured_planet_when_the_origin_was_lost() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        world.planets[1].resources = create_resource_map(100, 0, 0);
+
+        let fleet_name = world.planets[0].default_fleet_name();
+        world
+            .owned_fleet_mut(1, "planet_1", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 3, 5, 5)]).ships);
+        world
+            .move_fleet(1, "planet_1", &fleet_name, "planet_2")
+            .unwrap();
+        world.planets[0].set_owner(Some(2));
+        world.tick();
+
+        assert!(notifications(&mut world).contains(&String::from(
+            "planet_1 fleet lost planet_1, the loot stays on planet_2"
+        )));
+        assert_eq!(world.planets[1].owner, Some(1));
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Credits].amount,
+            100
+        );
+    }
+
+    #[test]
+    fn galaxy_generation_is_seeded() {
+        let config = GalaxyConfig::new(42, 12);
+        let galaxy = generate_galaxy(&config).unwrap();
+        let same_galaxy = generate_galaxy(&config).unwrap();
+        let other_galaxy = generate_galaxy(&GalaxyConfig::new(43, 12)).unwrap();
+
+        let layout = |planets: &[Planet]| -> Vec<(String, Position)> {
+            planets
+                .iter()
+                .map(|planet| (planet.name.clone(), planet.position.clone()))
+                .collect()
+        };
+        assert_eq!(galaxy.len(), 12);
+        assert_eq!(layout(&galaxy), layout(&same_galaxy));
+        assert_ne!(layout(&galaxy), layout(&other_galaxy));
+
+        for (index, planet) in galaxy.iter().enumerate() {
+            assert!(planet.position.x <= config.width && planet.position.y <= config.height);
+            assert!(!planet.name.contains(' '));
+            for other in &galaxy[index + 1..] {
+                assert_ne!(planet.name, other.name);
+                assert!(planet.position.distance(&other.position) >= config.min_distance as f64);
+            }
+        }
+    }
+
+    #[test]
+    fn crowded_galaxy_error() {
+        let mut config = GalaxyConfig::new(1, 10);
+        config.width = 5;
+        config.height = 5;
+        assert!(matches!(
+            generate_galaxy(&config),
+            Err(GameError::InvalidGalaxy(_))
+        ));
+    }
+
+    #[test]
+    fn oversized_galaxy_error() {
+        for planet_count in [MAX_PLANETS + 1, 4_294_967_296, usize::MAX] {
+            assert!(matches!(
+                generate_galaxy(&GalaxyConfig::new(1, planet_count)),
+                Err(GameError::InvalidGalaxy(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn home_planets_are_spread_out() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 5, 5),
+            Planet::new(String::from("planet_3"), 50, 50),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.register_player(3, String::from("carol")).unwrap();
+        world.register_player(4, String::from("dave")).unwrap();
+
+        assert_eq!(world.assign_home_planet(1), Ok(String::from("planet_1")));
+        assert_eq!(world.assign_home_planet(2), Ok(String::from("planet_3")));
+        assert_eq!(world.assign_home_planet(3), Ok(String::from("planet_2")));
+        assert_eq!(world.assign_home_planet(4), Err(GameError::NoFreePlanet));
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Credits)
+                .unwrap()
+                .amount,
+            100
+        );
+    }
+
+    #[test]
+    fn planet_traits_modify_building_output() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.traits.push(PlanetTrait::Ocean);
+        planet_1
+            .buildings
+            .push(Building::ResourceBuilding(ResourceBuilding {
+                building: BuildingData {
+                    name: String::from("Farm"),
+                    resource_cost: create_resource_map(0, 0, 0),
+                    level: 1,
+                    build_time: 0,
+                    workers: 0,
+                    catalog_id: None,
+                },
+                create_type: create_resource_map(0, 10, 8),
+            }));
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+
+        let planet = &world.planets[0];
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 15);
+        assert_eq!(
+            planet.get_resource(ResourceType::Minerals).unwrap().amount,
+            6
+        );
+    }
+
+    #[test]
+    fn deposits_limit_and_run_out() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1
+            .deposits
+            .insert(ResourceType::Minerals, Deposit::new(Some(25), Some(15)));
+        planet_1.buildings.push(timed_mine("Mine", 0));
+        planet_1.buildings.push(timed_mine("Mine", 0));
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            15
+        );
+        assert!(world.drain_events().is_empty());
+
+        world.tick();
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            25
+        );
+        assert!(world.planets[0].deposits[&ResourceType::Minerals].is_depleted());
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "The Minerals deposit on planet_1 is depleted")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+
+    #[test]
+    fn population_eats_and_grows() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.population.size = 10;
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(0, 25, 0)).unwrap();
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        let planet = &world.planets[0];
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 15);
+        assert_eq!(planet.population.size, 11);
+
+        // 15 food feeds everyone once but leaves nothing to grow on
+        world.tick();
+        let planet = &world.planets[0];
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 4);
+        assert_eq!(planet.population.size, 11);
+        assert!(world.drain_events().is_empty());
+    }
+
+    #[test]
+    fn claimed_planet_gets_its_first_settlers() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(0, 5, 0)).unwrap();
+        let mut planet_2: Planet = Planet::new(String::from("planet_2"), 5, 0);
+        Planet::add_resource(&mut planet_2.resources, create_resource_map(0, 5, 0)).unwrap();
+
+        let mut world = GameWorld::new(vec![planet_1, planet_2]);
+        world.register_player(111, String::from("alice")).unwrap();
+        world.claim_planet(111, "planet_1").unwrap();
+        world.tick();
+        assert_eq!(world.planets[0].population.size, 1);
+        // nobody moves to a planet without an owner
+        assert_eq!(world.planets[1].population.size, 0);
+
+        world.tick();
+        let planet = &world.planets[0];
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 4);
+        assert_eq!(planet.population.size, 2);
+    }
+
+    #[test]
+    fn population_starves_without_food() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.population.size = 10;
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(0, 4, 0)).unwrap();
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        let planet = &world.planets[0];
+        assert_eq!(planet.get_resource(ResourceType::Food).unwrap().amount, 0);
+        assert_eq!(planet.population.size, 4);
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "6 inhabitants of planet_1 starved")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+    }
+
+    #[test]
+    fn buildings_need_workers() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.population.size = 3;
+        Planet::add_resource(&mut planet_1.resources, create_resource_map(0, 100, 0)).unwrap();
+        for _ in 0..2 {
+            let mut mine = timed_mine("Mine", 0);
+            mine.get_building_data_mut().workers = 2;
+            planet_1.buildings.push(mine);
+        }
+        assert_eq!(planet_1.staffed_buildings(), vec![true, false]);
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            10
+        );
+        // the population grew to 4 this tick, enough for both mines
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            30
+        );
+    }
+
+    #[test]
+    fn storage_caps_waste_excess_production() {
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.base_storage = 15;
+        planet_1.buildings.push(timed_mine("Mine", 0));
+
+        let mut world = GameWorld::new(vec![planet_1]);
+        world.tick();
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            15
+        );
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(
+                    message,
+                    "Storage on planet_1 is full, 5 Minerals went to waste"
+                )
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        // resources that aren't produced are kept even past the capacity
+        let capacity = world.planets[0].storage_capacity(ResourceType::Minerals);
+        assert_eq!(
+            world.planets[0].apply_transaction("Gift", &create_resource_map(0, 0, 10)),
+            Ok(())
+        );
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Minerals].amount,
+            capacity + 10
+        );
+        assert_eq!(world.planets[0].storage_room(ResourceType::Minerals), 0);
+    }
+
+    #[test]
+    fn cancelled_order_escrow_returns_into_full_storage() {
+        let mut world = trade_world();
+        world.planets[0].resources = create_resource_map(0, 0, 10);
+        let id = world
+            .place_order(
+                1,
+                "planet_1",
+                OrderSide::Sell,
+                ResourceType::Minerals,
+                10,
+                5,
+            )
+            .unwrap();
+
+        let capacity = world.planets[0].storage_capacity(ResourceType::Minerals);
+        world.planets[0]
+            .resources
+            .get_mut(&ResourceType::Minerals)
+            .unwrap()
+            .amount = capacity;
+        world.cancel_order(1, id).unwrap();
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Minerals].amount,
+            capacity + 10
+        );
+    }
+
+    #[test]
+    fn warehouse_raises_storage() {
+        let catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        let mut warehouse = catalog.get("warehouse").unwrap().create();
+        warehouse.get_building_data_mut().level = 2;
+
+        let mut planet_1: Planet = Planet::new(String::from("planet_1"), 0, 0);
+        planet_1.buildings.push(warehouse);
+        assert_eq!(planet_1.storage_capacity(ResourceType::Food), 2000);
+        assert_eq!(planet_1.storage_capacity(ResourceType::Minerals), 2000);
+    }
+
+    fn research_world() -> GameWorld {
+        let mut world = GameWorld::new(vec![Planet::new(String::from("planet_1"), 0, 0)]);
+        world.catalog =
+            BuildingCatalog::from_toml_str(include_str!("../../assets/buildings.toml")).unwrap();
+        world
+            .set_research(
+                ResearchTree::from_toml_str(include_str!("../../assets/research.toml")).unwrap(),
+            )
+            .unwrap();
+        world.register_player(1, String::from("alice")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world
+    }
+
+    #[test]
+    fn research_tree_must_fit_the_catalog_and_resources() {
+        let mut world = research_world();
+        // a type another config registered, but this world doesn't have
+        ResourceType::from_id("Unobtainium");
+        for (unlocks, error) in [
+            (
+                r#"buildings = ["star_forge"]"#,
+                "tech \"forging\" unlocks unknown building \"star_forge\"",
+            ),
+            (
+                r#"blueprints = ["dreadnought"]"#,
+                "tech \"forging\" unlocks unknown blueprint \"dreadnought\"",
+            ),
+            (
+                "bonuses = { Unobtainium = 10 }",
+                "tech \"forging\" gives a bonus to unknown resource \"Unobtainium\"",
+            ),
+        ] {
+            let research = ResearchTree::from_toml_str(&format!(
+                "[[techs]]\nid = \"forging\"\nname = \"Forging\"\ncost = 10\n{}",
+                unlocks
+            ))
+            .unwrap();
+            assert_eq!(
+                world.set_research(research),
+                Err(GameError::InvalidResearch(String::from(error)))
+            );
+        }
+        assert!(world.research.get("deep_mining").is_some());
+    }
+
+    #[test]
+    fn research_unlocks_buildings_and_bonuses() {
+        let mut world = research_world();
+        let research = research_resource();
+        let mut points = HashMap::new();
+        points.insert(
+            research,
+            Resource {
+                resource_type: research,
+                amount: 30,
+            },
+        );
+        world.planets[0].apply_transaction("Gift", &points).unwrap();
+
+        let available: Vec<&str> = world
+            .available_techs(1)
+            .unwrap()
+            .iter()
+            .map(|tech| tech.id.as_str())
+            .collect();
+        assert_eq!(available, vec!["deep_mining", "hydroponics"]);
+        assert_eq!(world.locked_techs(1).unwrap().len(), 3);
+        assert_eq!(
+            world.start_research(1, "spaceflight"),
+            Err(GameError::TechLocked(String::from("Deep Mining")))
+        );
+        assert_eq!(
+            world.build_from_catalog(1, "planet_1", "shipyard"),
+            Err(GameError::TechLocked(String::from("Spaceflight")))
+        );
+
+        assert_eq!(world.start_research(1, "deep_mining"), Ok(()));
+        world.tick();
+        assert_eq!(world.techs_of(1).unwrap()[0].1, TechStatus::Researching(30));
+
+        world.planets[0].apply_transaction("Gift", &points).unwrap();
+        world.tick();
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "alice finished researching Deep Mining")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert_eq!(world.planets[0].get_resource(research).unwrap().amount, 20);
+        assert_eq!(
+            world.start_research(1, "deep_mining"),
+            Err(GameError::AlreadyResearched(String::from("Deep Mining")))
+        );
+        assert_eq!(world.start_research(1, "spaceflight"), Ok(()));
+
+        // deep mining adds 20% to mineral output
+        world.planets[0].buildings.push(timed_mine("Mine", 0));
+        world.tick();
+        assert_eq!(
+            world.planets[0]
+                .get_resource(ResourceType::Minerals)
+                .unwrap()
+                .amount,
+            12
+        );
+    }
+
+    #[test]
+    fn refit_needs_the_blueprint_tech() {
+        let mut world = research_world();
+        let shipyard = world.catalog.get("shipyard").unwrap().create();
+        world.planets[0].buildings.push(shipyard);
+        world.planets[0].buildings.push(timed_mine("Mine", 0));
+
+        assert_eq!(
+            world.refit_building(1, "planet_1", 0, "frigate"),
+            Err(GameError::TechLocked(String::from("Heavy Hulls")))
+        );
+        world
+            .players
+            .get_mut(&1)
+            .unwrap()
+            .research
+            .researched
+            .insert(String::from("heavy_hulls"));
+        assert_eq!(
+            world.refit_building(1, "planet_1", 1, "frigate"),
+            Err(GameError::WrongBlueprint(
+                String::from("Frigate"),
+                String::from("Mine")
+            ))
+        );
+        assert_eq!(world.refit_building(1, "planet_1", 0, "frigate"), Ok(()));
+        match &world.planets[0].buildings[0] {
+            Building::MilitaryBuilding(mil) => {
+                assert_eq!(mil.create_type.get_creation().name, "Frigate");
+                assert_eq!(mil.progress_required_to_create_military, 30);
+            }
+            _ => panic!("the shipyard should be a military building"),
+        }
+    }
+
+    fn trade_world() -> GameWorld {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 0, 20),
+            Planet::new(String::from("planet_3"), 2, 10),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.register_player(3, String::from("carol")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        world.claim_planet(3, "planet_3").unwrap();
+        world.planets[0].resources = create_resource_map(25, 0, 0);
+        world
+    }
+
+    fn notifications(world: &mut GameWorld) -> Vec<String> {
+        world
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                TickResult::Notification(message) => Some(message),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn convoys_ship_cargo_along_trade_routes() {
+        let mut world = trade_world();
+        let cargo = HashMap::from([(ResourceType::Credits, 10)]);
+
+        assert_eq!(
+            world.create_trade_route(1, "planet_1", "planet_1", cargo.clone(), 3),
+            Err(GameError::InvalidTradeRoute(String::from(
+                "a route needs two different planets"
+            )))
+        );
+        assert_eq!(
+            world.create_trade_route(1, "planet_2", "planet_1", cargo.clone(), 3),
+            Err(GameError::NotOwner(String::from("planet_2")))
+        );
+        assert_eq!(
+            world.create_trade_route(
+                1,
+                "planet_1",
+                "planet_2",
+                HashMap::from([(ResourceType::from_id("Unobtainium"), 1)]),
+                3
+            ),
+            Err(GameError::UnknownResource(ResourceType::from_id(
+                "Unobtainium"
+            )))
+        );
+        assert_eq!(
+            world.create_trade_route(1, "planet_1", "planet_2", cargo.clone(), 3),
+            Ok(1)
+        );
+
+        // 20 units of distance take 5 ticks, a convoy leaves every 3 ticks
+        world.tick();
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Credits].amount,
+            15
+        );
+        assert_eq!(world.convoys_of(1).next().unwrap().ticks_remaining(), 4);
+        for _ in 0..3 {
+            world.tick();
+        }
+        assert_eq!(world.planets[0].resources[&ResourceType::Credits].amount, 5);
+        assert_eq!(world.convoys.len(), 2);
+        notifications(&mut world);
+
+        world.tick();
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Credits].amount,
+            10
+        );
+        assert_eq!(
+            notifications(&mut world),
+            vec![String::from("The convoy from planet_1 arrived at planet_2")]
+        );
+
+        world.tick();
+        world.tick();
+        assert_eq!(
+            notifications(&mut world),
+            vec![String::from(
+                "No convoy left planet_1 for planet_2: Not enough resources, missing 5 Credits"
+            )]
+        );
+
+        world.cancel_trade_route(1, 1).unwrap();
+        assert_eq!(
+            world.cancel_trade_route(1, 1),
+            Err(GameError::UnknownTradeRoute(1))
+        );
+        world.tick();
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Credits].amount,
+            20
+        );
+        assert!(world.convoys.is_empty());
+    }
+
+    #[test]
+    fn hostile_fleets_intercept_convoys() {
+        let mut world = trade_world();
+        let fleet_name = world.planets[2].default_fleet_name();
+        world
+            .owned_fleet_mut(3, "planet_3", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 1, 5, 5)]).ships);
+        world
+            .create_trade_route(
+                1,
+                "planet_1",
+                "planet_2",
+                HashMap::from([(ResourceType::Credits, 10)]),
+                10,
+            )
+            .unwrap();
+
+        world.tick();
+        notifications(&mut world);
+        world.tick();
+        assert_eq!(
+            notifications(&mut world),
+            vec![String::from(
+                "The convoy from planet_1 to planet_2 was intercepted by planet_3 fleet"
+            )]
+        );
+        assert!(world.convoys.is_empty());
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Credits].amount,
+            15
+        );
+        assert_eq!(world.planets[1].resources[&ResourceType::Credits].amount, 0);
+    }
+
+    #[test]
+    fn market_matches_orders_with_escrow_and_fees() {
+        let mut world = trade_world();
+        world.planets[0].resources = create_resource_map(0, 0, 10);
+        world.planets[1].resources = create_resource_map(50, 0, 0);
+
+        assert_eq!(
+            world.place_order(
+                1,
+                "planet_1",
+                OrderSide::Sell,
+                ResourceType::Minerals,
+                20,
+                5
+            ),
+            Err(GameError::InsufficientResources(HashMap::from([(
+                ResourceType::Minerals,
+                10
+            )])))
+        );
+        assert_eq!(
+            world.place_order(1, "planet_1", OrderSide::Buy, ResourceType::Credits, 1, 1),
+            Err(GameError::InvalidOrder(String::from(
+                "Credits can't be traded for Credits"
+            )))
+        );
+        assert_eq!(
+            world.place_order(
+                1,
+                "planet_1",
+                OrderSide::Buy,
+                ResourceType::Food,
+                100_000,
+                100_000
+            ),
+            Err(GameError::InvalidOrder(String::from(
+                "the order is worth too many Credits"
+            )))
+        );
+        assert_eq!(
+            world.place_order(
+                1,
+                "planet_1",
+                OrderSide::Sell,
+                ResourceType::Minerals,
+                10,
+                5
+            ),
+            Ok(1)
+        );
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Minerals].amount,
+            0
+        );
+        assert_eq!(
+            world.place_order(2, "planet_2", OrderSide::Buy, ResourceType::Minerals, 6, 7),
+            Ok(2)
+        );
+        assert_eq!(world.planets[1].resources[&ResourceType::Credits].amount, 8);
+
+        // the sell order came first so its price is used, 5% of 30 Credits is kept
+        world.tick();
+        assert_eq!(
+            notifications(&mut world),
+            vec![String::from(
+                "planet_1 sold 6 Minerals to planet_2 for 5 Credits each"
+            )]
+        );
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Credits].amount,
+            29
+        );
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Minerals].amount,
+            6
+        );
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Credits].amount,
+            20
+        );
+        assert_eq!(world.orders_of(1).next().unwrap().quantity, 4);
+        assert_eq!(world.orders_of(2).count(), 0);
+        assert_eq!(
+            world.market.price_history(ResourceType::Minerals, 10),
+            &[PricePoint {
+                tick: 1,
+                price: 5,
+                volume: 6
+            }]
+        );
+
+        // players never trade with themselves
+        world.planets[0].resources = create_resource_map(50, 0, 0);
+        world
+            .place_order(1, "planet_1", OrderSide::Buy, ResourceType::Minerals, 4, 9)
+            .unwrap();
+        world.tick();
+        assert_eq!(world.orders_of(1).count(), 2);
+
+        world.cancel_order(1, 1).unwrap();
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Minerals].amount,
+            4
+        );
+        assert_eq!(world.cancel_order(2, 3), Err(GameError::UnknownOrder(3)));
+    }
+
+    #[test]
+    fn market_fees_of_large_trades_dont_overflow() {
+        let mut market = Market::default();
+        market.add_order(
+            1,
+            "planet_1",
+            OrderSide::Sell,
+            ResourceType::Food,
+            100_000,
+            20_000,
+        );
+        market.add_order(
+            2,
+            "planet_2",
+            OrderSide::Buy,
+            ResourceType::Food,
+            100_000,
+            21_000,
+        );
+        let fills = market.match_orders();
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].fee, 100_000_000);
+        assert_eq!(fills[0].proceeds(), 1_900_000_000);
+        assert_eq!(fills[0].refund(), 100_000_000);
+    }
+
+    #[test]
+    fn treaties_stop_fleets_from_fighting() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        let fleet_name = world.planets[0].default_fleet_name();
+        world
+            .owned_fleet_mut(1, "planet_1", &fleet_name)
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 3, 5, 5)]).ships);
+
+        assert_eq!(
+            world.propose_treaty(1, 1, TreatyKind::Alliance, None),
+            Err(GameError::SelfDiplomacy)
+        );
+        let pact = world
+            .propose_treaty(1, 2, TreatyKind::NonAggression, None)
+            .unwrap();
+        assert_eq!(
+            world.accept_proposal(1, pact),
+            Err(GameError::UnknownProposal(pact))
+        );
+        world.accept_proposal(2, pact).unwrap();
+        assert_eq!(
+            world.move_fleet(1, "planet_1", &fleet_name, "planet_2"),
+            Err(GameError::PactForbidsAttack(String::from("bob")))
+        );
+
+        // an alliance replaces the pact, allied fleets may visit without a fight
+        let alliance = world
+            .propose_treaty(2, 1, TreatyKind::Alliance, Some(5))
+            .unwrap();
+        world.accept_proposal(1, alliance).unwrap();
+        assert_eq!(
+            world.diplomacy.relation(1, 2),
+            Relation::Treaty(TreatyKind::Alliance)
+        );
+        assert_eq!(
+            world.move_fleet(1, "planet_1", &fleet_name, "planet_2"),
+            Ok(1)
+        );
+        world.tick();
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => {
+                assert_eq!(message, "planet_1 fleet arrived at planet_2")
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert_eq!(world.planets[1].owner, Some(2));
+        assert_eq!(world.planets[1].military[&fleet_name].ships.len(), 3);
+
+        assert_eq!(
+            world.declare_war(2, 1).unwrap().map(|treaty| treaty.kind),
+            Some(TreatyKind::Alliance)
+        );
+        assert_eq!(world.diplomacy.relation(1, 2), Relation::War);
+        assert_eq!(
+            world.declare_war(1, 2),
+            Err(GameError::AlreadyAtWar(String::from("bob")))
+        );
+    }
+
+    #[test]
+    fn treaties_expire() {
+        let mut world = GameWorld::new(vec![]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        let id = world
+            .propose_treaty(1, 2, TreatyKind::NonAggression, Some(2))
+            .unwrap();
+        world.accept_proposal(2, id).unwrap();
+
+        world.tick();
+        assert!(world.drain_events().is_empty());
+        world.tick();
+        match &world.drain_events()[..] {
+            [TickResult::Notification(message)] => assert_eq!(
+                message,
+                "The non-aggression pact between alice and bob has expired"
+            ),
+            events => panic!("unexpected events {:?}", events),
+        }
+        assert_eq!(world.diplomacy.relation(1, 2), Relation::Neutral);
+    }
+
+    #[test]
+    fn fleets_are_split_merged_and_disbanded_by_id() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        let home = world.planets[0].military[&world.planets[0].default_fleet_name()].id;
+        world.planets[0]
+            .military
+            .get_mut("planet_1 fleet")
+            .unwrap()
+            .ships
+            .extend(create_fleet_of(&[("Corvette", 3, 5, 5), ("Scout", 1, 1, 1)]).ships);
+
+        assert_eq!(
+            world.split_fleet(1, home, "corvette", 4, String::from("Strike")),
+            Err(GameError::NotEnoughUnits(String::from("corvette"), 4))
+        );
+        let strike = world
+            .split_fleet(1, home, "corvette", 2, String::from("Strike"))
+            .unwrap();
+        assert_eq!(
+            world.planets[0].military["Strike"].unit_counts()["Corvette"],
+            2
+        );
+        assert_eq!(world.planets[0].military["planet_1 fleet"].ships.len(), 2);
+        assert_eq!(
+            world.split_fleet(2, strike, "corvette", 1, String::from("Stolen")),
+            Err(GameError::NotOwner(String::from("Strike")))
+        );
+
+        // the id stays with the fleet whatever it is called
+        world
+            .rename_fleet(1, home, String::from("Home Guard"))
+            .unwrap();
+        assert_eq!(
+            world.locate_fleet(1, home),
+            Ok((0, String::from("Home Guard")))
+        );
+        assert_eq!(
+            world.disband_fleet(1, home),
+            Err(GameError::FleetNotEmpty(String::from("Home Guard")))
+        );
+        world.merge_fleets(1, home, strike).unwrap();
+        assert_eq!(world.planets[0].military["Strike"].ships.len(), 4);
+        assert_eq!(
+            world.locate_fleet(1, home),
+            Err(GameError::UnknownFleetId(home))
+        );
+
+        assert_eq!(world.move_fleet(1, "planet_1", "Strike", "planet_2"), Ok(1));
+        assert_eq!(
+            world.disband_fleet(1, strike),
+            Err(GameError::FleetInTransit(String::from("Strike")))
+        );
+        let empty = world
+            .create_fleet(1, "planet_1", String::from("Reserve"))
+            .unwrap();
+        world.disband_fleet(1, empty).unwrap();
+        assert!(world.planets[0].military.is_empty());
+    }
+
+    #[test]
+    fn military_buildings_send_ships_to_their_rally_fleet() {
+        let mut world = GameWorld::new(vec![Planet::new(String::from("planet_1"), 0, 0)]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.planets[0]
+            .build_building(Building::MilitaryBuilding(MilitaryBuilding::new(
+                String::from("Shipyard"),
+                HashMap::new(),
+                MilitaryCreationTypes::Ship(MilitaryCreation::new(
+                    String::from("Corvette"),
+                    1,
+                    5,
+                    5,
+                )),
+                HashMap::new(),
+                1,
+                1,
+            )))
+            .unwrap();
+        let rally = world
+            .create_fleet(1, "planet_1", String::from("Rally"))
+            .unwrap();
+        assert_eq!(
+            world.set_rally_fleet(1, "planet_1", 1, Some(rally)),
+            Err(GameError::UnknownBuilding(1))
+        );
+        world
+            .set_rally_fleet(1, "planet_1", 0, Some(rally))
+            .unwrap();
+
+        world.tick();
+        assert_eq!(world.planets[0].military["Rally"].ships.len(), 1);
+
+        // without its rally fleet the shipyard falls back to a new default fleet
+        world.disband_fleet(1, 1).unwrap();
+        assert_eq!(
+            world.merge_fleets(1, rally, rally),
+            Err(GameError::SameFleet)
+        );
+        world.planets[0].military.remove("Rally");
+        world.tick();
+        let fleet = &world.planets[0].military["planet_1 fleet"];
+        assert_eq!(fleet.ships.len(), 1);
+        assert_eq!(fleet.owner, Some(1));
+        assert!(fleet.id > rally);
+    }
+
+    #[test]
+    fn damaged_units_are_repaired_at_friendly_planets() {
+        let mut world = GameWorld::new(vec![
+            Planet::new(String::from("planet_1"), 0, 0),
+            Planet::new(String::from("planet_2"), 3, 4),
+        ]);
+        world.register_player(1, String::from("alice")).unwrap();
+        world.register_player(2, String::from("bob")).unwrap();
+        world.claim_planet(1, "planet_1").unwrap();
+        world.claim_planet(2, "planet_2").unwrap();
+        world.planets[0].resources = create_resource_map(0, 0, 3);
+        world.planets[1].resources = create_resource_map(0, 0, 10);
+
+        let mut wounded = create_fleet_of(&[("Corvette", 2, 5, 5)]);
+        wounded.owner = Some(1);
+        for ship in wounded.ships.iter_mut() {
+            ship.get_creation_mut().take_damage(4);
+        }
+        world.planets[0]
+            .military
+            .get_mut("planet_1 fleet")
+            .unwrap()
+            .ships
+            .extend(wounded.ships);
+        let mut visitors = create_fleet_of(&[("Corvette", 1, 5, 5)]);
+        visitors.owner = Some(1);
+        visitors.ships[0].get_creation_mut().take_damage(4);
+        world.planets[1].station_fleet("Visitors", visitors);
+
+        // 3 minerals pay for 3 hit points, the visitors aren't repaired by a stranger
+        world.tick();
+        let hp = |world: &GameWorld, planet: usize, fleet: &str| -> Vec<u32> {
+            world.planets[planet].military[fleet]
+                .ships
+                .iter()
+                .map(|ship| ship.get_creation().hp())
+                .collect()
+        };
+        assert_eq!(hp(&world, 0, "planet_1 fleet"), vec![3, 2]);
+        assert_eq!(
+            world.planets[0].resources[&ResourceType::Minerals].amount,
+            0
+        );
+        assert_eq!(hp(&world, 1, "Visitors"), vec![1]);
+
+        let alliance = world
+            .propose_treaty(1, 2, TreatyKind::Alliance, None)
+            .unwrap();
+        world.accept_proposal(2, alliance).unwrap();
+        world.tick();
+        world.tick();
+        assert_eq!(hp(&world, 1, "Visitors"), vec![5]);
+        assert_eq!(
+            world.planets[1].resources[&ResourceType::Minerals].amount,
+            6
+        );
+    }
+
+    #[test]
+    fn invalid_research_tree_error() {
+        assert!(matches!(
+            ResearchTree::from_toml_str(
+                r#"
+                [[techs]]
+                id = "a"
+                name = "A"
+                cost = 10
+                prerequisites = ["b"]
+
+                [[techs]]
+                id = "b"
+                name = "B"
+                cost = 10
+                "#
+            ),
+            Err(GameError::InvalidResearch(_))
+        ));
+    }
 }
@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::resource::ResourceType;
+
+pub const LEDGER_HISTORY_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub tick: u64,
+    pub source: String,
+    pub resource_type: ResourceType,
+    /// positive for credits to the planet, negative for debits
+    pub amount: i32,
+}
+
+/// Keeps the most recent resource movements of a planet so players can see
+/// where their resources came from and went to.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ResourceLedger {
+    pub current_tick: u64,
+    pub entries: VecDeque<LedgerEntry>,
+}
+
+impl ResourceLedger {
+    pub fn advance_tick(&mut self) {
+        self.current_tick += 1;
+    }
+
+    pub fn record(&mut self, source: &str, resource_type: ResourceType, amount: i32) {
+        self.entries.push_back(LedgerEntry {
+            tick: self.current_tick,
+            source: source.to_string(),
+            resource_type,
+            amount,
+        });
+
+        while self.entries.len() > LEDGER_HISTORY_LIMIT {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn entries_for(&self, resource_type: ResourceType) -> impl Iterator<Item = &LedgerEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.resource_type == resource_type)
+    }
+
+    pub fn entries_from<'a>(&'a self, source: &'a str) -> impl Iterator<Item = &'a LedgerEntry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.source == source)
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::PlayerId;
+use super::resource::ResourceType;
+
+/// Price points kept per resource type, older ones are dropped.
+pub const MAX_PRICE_HISTORY: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl fmt::Display for OrderSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Buy => write!(f, "buy"),
+            Self::Sell => write!(f, "sell"),
+        }
+    }
+}
+
+/// An offer to trade `quantity` of `resource_type` for `price` Credits each.
+/// What the order offers is held in escrow until it is filled or cancelled,
+/// the resources of a sell order and the Credits of a buy order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Order {
+    pub id: u32,
+    pub owner: PlayerId,
+    /// the planet paying into the escrow and receiving what was traded
+    pub planet: String,
+    pub side: OrderSide,
+    pub resource_type: ResourceType,
+    /// what is left to trade
+    pub quantity: i32,
+    pub price: i32,
+}
+
+impl Order {
+    /// What the order holds in escrow for what is left of it. Orders are only
+    /// placed when their escrow fits, it can only shrink from there.
+    pub fn escrow(&self) -> (ResourceType, i32) {
+        match self.side {
+            OrderSide::Buy => (ResourceType::Credits, credits(self.quantity, self.price)),
+            OrderSide::Sell => (self.resource_type, self.quantity),
+        }
+    }
+}
+
+/// A buy and a sell order matched for `quantity` at `price`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fill {
+    pub buy: Order,
+    pub sell: Order,
+    pub quantity: i32,
+    pub price: i32,
+    /// Credits taken from the seller's proceeds
+    pub fee: i32,
+}
+
+impl Fill {
+    /// Credits the buyer gets back because the trade was cheaper than they offered.
+    pub fn refund(&self) -> i32 {
+        credits(self.quantity, self.buy.price - self.price)
+    }
+
+    pub fn proceeds(&self) -> i32 {
+        credits(self.quantity, self.price) - self.fee
+    }
+}
+
+/// Credits for `quantity` at `price` each, computed in `i64` so large orders
+/// saturate instead of overflowing.
+fn credits(quantity: i32, price: i32) -> i32 {
+    (quantity as i64 * price as i64).clamp(i32::MIN as i64, i32::MAX as i64) as i32
+}
+
+/// The trades of one resource type in one tick.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PricePoint {
+    pub tick: u32,
+    /// average price weighted by quantity
+    pub price: i32,
+    pub volume: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Market {
+    pub orders: Vec<Order>,
+    /// share of every sale's Credits the market keeps
+    pub fee_percent: i32,
+    pub history: HashMap<ResourceType, Vec<PricePoint>>,
+    pub tick: u32,
+    pub next_order_id: u32,
+}
+
+impl Default for Market {
+    fn default() -> Self {
+        Self {
+            orders: vec![],
+            fee_percent: 5,
+            history: HashMap::new(),
+            tick: 0,
+            next_order_id: 1,
+        }
+    }
+}
+
+impl Market {
+    /// Adds an order whose escrow was already paid, returns its id.
+    pub fn add_order(
+        &mut self,
+        owner: PlayerId,
+        planet: &str,
+        side: OrderSide,
+        resource_type: ResourceType,
+        quantity: i32,
+        price: i32,
+    ) -> u32 {
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+        self.orders.push(Order {
+            id,
+            owner,
+            planet: planet.to_string(),
+            side,
+            resource_type,
+            quantity,
+            price,
+        });
+        id
+    }
+
+    pub fn remove_order(&mut self, owner: PlayerId, id: u32) -> Option<Order> {
+        let index = self
+            .orders
+            .iter()
+            .position(|order| order.id == id && order.owner == owner)?;
+        Some(self.orders.remove(index))
+    }
+
+    /// The buy orders of `resource_type` from the highest price down and the
+    /// sell orders from the lowest price up, older orders first on equal prices.
+    pub fn order_book(&self, resource_type: ResourceType) -> (Vec<&Order>, Vec<&Order>) {
+        let side = |side: OrderSide| {
+            self.orders
+                .iter()
+                .filter(move |order| order.resource_type == resource_type && order.side == side)
+        };
+        let mut buys: Vec<&Order> = side(OrderSide::Buy).collect();
+        buys.sort_by_key(|order| (-order.price, order.id));
+        let mut sells: Vec<&Order> = side(OrderSide::Sell).collect();
+        sells.sort_by_key(|order| (order.price, order.id));
+        (buys, sells)
+    }
+
+    /// The last `count` price points of `resource_type`, oldest first.
+    pub fn price_history(&self, resource_type: ResourceType, count: usize) -> &[PricePoint] {
+        match self.history.get(&resource_type) {
+            Some(history) => &history[history.len().saturating_sub(count)..],
+            None => &[],
+        }
+    }
+
+    /// Matches the best buy and sell orders of every resource type for as long
+    /// as their prices overlap. Orders of the same player never match, trades
+    /// happen at the price of the older order. Filled orders are removed, the
+    /// caller delivers what was traded.
+    pub fn match_orders(&mut self) -> Vec<Fill> {
+        self.tick += 1;
+        let mut fills = vec![];
+        let mut resource_types: Vec<ResourceType> = self
+            .orders
+            .iter()
+            .map(|order| order.resource_type)
+            .collect();
+        resource_types.sort_by_key(|resource_type| resource_type.id());
+        resource_types.dedup();
+
+        for resource_type in resource_types {
+            let mut traded = 0;
+            let mut volume = 0;
+            while let Some(fill) = self.match_best(resource_type) {
+                traded += fill.price as i64 * fill.quantity as i64;
+                volume += fill.quantity;
+                fills.push(fill);
+            }
+            if volume > 0 {
+                let history = self.history.entry(resource_type).or_default();
+                history.push(PricePoint {
+                    tick: self.tick,
+                    price: (traded / volume as i64) as i32,
+                    volume,
+                });
+                if history.len() > MAX_PRICE_HISTORY {
+                    history.remove(0);
+                }
+            }
+        }
+        fills
+    }
+
+    fn match_best(&mut self, resource_type: ResourceType) -> Option<Fill> {
+        let (buys, sells) = self.order_book(resource_type);
+        let (buy, sell) = buys.iter().find_map(|buy| {
+            sells
+                .iter()
+                .take_while(|sell| sell.price <= buy.price)
+                .find(|sell| sell.owner != buy.owner)
+                .map(|sell| ((*buy).clone(), (*sell).clone()))
+        })?;
+
+        let quantity = buy.quantity.min(sell.quantity);
+        let price = if buy.id < sell.id {
+            buy.price
+        } else {
+            sell.price
+        };
+        let fee =
+            (price as i64 * quantity as i64 * self.fee_percent.clamp(0, 100) as i64 / 100) as i32;
+        for order in self.orders.iter_mut() {
+            if order.id == buy.id || order.id == sell.id {
+                order.quantity -= quantity;
+            }
+        }
+        self.orders.retain(|order| order.quantity > 0);
+
+        Some(Fill {
+            buy,
+            sell,
+            quantity,
+            price,
+            fee,
+        })
+    }
+}
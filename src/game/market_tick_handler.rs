@@ -0,0 +1,100 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    error::GameError,
+    market::Market,
+    planet::Planet,
+    resource::{resource_map, ResourceType},
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct MarketTickRequest<'a> {
+    pub planets: &'a mut Vec<Planet>,
+    pub market: &'a mut Market,
+}
+
+impl RequestHandler<MarketTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: MarketTickRequest) {
+        // an order is cancelled once its owner lost the planet, the escrow goes
+        // back to the planet whoever owns it now
+        let (open, closed) = std::mem::take(&mut req.market.orders)
+            .into_iter()
+            .partition(|order| {
+                req.planets
+                    .iter()
+                    .any(|planet| planet.name == order.planet && planet.owner == Some(order.owner))
+            });
+        req.market.orders = open;
+        for order in closed {
+            let (resource_type, amount) = order.escrow();
+            deliver(self, req.planets, &order.planet, &[(resource_type, amount)]);
+            self.publish(TickEvent::new(
+                [Some(order.owner)],
+                TickResult::Notification(format!(
+                    "The market order #{} of {} has been cancelled",
+                    order.id, order.planet
+                )),
+            ));
+        }
+
+        for fill in req.market.match_orders() {
+            deliver(
+                self,
+                req.planets,
+                &fill.buy.planet,
+                &[
+                    (fill.buy.resource_type, fill.quantity),
+                    (ResourceType::Credits, fill.refund()),
+                ],
+            );
+            deliver(
+                self,
+                req.planets,
+                &fill.sell.planet,
+                &[(ResourceType::Credits, fill.proceeds())],
+            );
+            self.publish(TickEvent::new(
+                [Some(fill.sell.owner), Some(fill.buy.owner)],
+                TickResult::Notification(format!(
+                    "{} sold {} {} to {} for {} Credits each",
+                    fill.sell.planet,
+                    fill.quantity,
+                    fill.sell.resource_type,
+                    fill.buy.planet,
+                    fill.price
+                )),
+            ));
+        }
+    }
+}
+
+fn deliver(
+    mediator: &BasicMediator<TickEvent>,
+    planets: &mut [Planet],
+    planet_name: &str,
+    amounts: &[(ResourceType, i32)],
+) {
+    let mut delivery: HashMap<ResourceType, i32> = HashMap::new();
+    for (resource_type, amount) in amounts.iter().filter(|(_, amount)| *amount > 0) {
+        *delivery.entry(*resource_type).or_insert(0) += amount;
+    }
+    if delivery.is_empty() {
+        return;
+    }
+
+    let planet = match planets.iter_mut().find(|planet| planet.name == planet_name) {
+        Some(planet) => planet,
+        None => {
+            mediator.publish(TickEvent::new(
+                [],
+                TickResult::Error(GameError::UnknownPlanet(planet_name.to_string())),
+            ));
+            return;
+        }
+    };
+    if let Err(err) = planet.apply_transaction("Market", &resource_map(&delivery)) {
+        mediator.publish(TickEvent::new([planet.owner], TickResult::Error(err)));
+    }
+}
@@ -1,17 +1,70 @@
-#[derive(Debug, Clone)]
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::player::PlayerId;
+use super::position::Position;
+
+/// Identifies a fleet for as long as it exists, whatever it is called and
+/// wherever it goes.
+pub type FleetId = u32;
+
+/// Distance a fleet covers per tick unless configured otherwise.
+pub const DEFAULT_FLEET_SPEED: u32 = 5;
+
+/// Experience every unit surviving a battle gains.
+pub const EXPERIENCE_PER_BATTLE: u32 = 5;
+/// Experience every surviving unit gains for each enemy unit its side destroyed.
+pub const EXPERIENCE_PER_KILL: u32 = 10;
+/// A unit of level `n` needs `n * EXPERIENCE_PER_LEVEL` experience for the next level.
+pub const EXPERIENCE_PER_LEVEL: u32 = 30;
+pub const MAX_UNIT_LEVEL: u32 = 5;
+/// Attack and defence a unit gains per level, in percent and at least 1.
+pub const VETERANCY_BONUS_PERCENT: u32 = 20;
+/// Hit points a damaged unit regains per tick at a friendly planet.
+pub const REPAIR_PER_TICK: u32 = 2;
+/// Minerals a repair costs per hit point.
+pub const REPAIR_COST_PER_HP: i32 = 1;
+
+fn default_fleet_speed() -> u32 {
+    DEFAULT_FLEET_SPEED
+}
+
+/// A unit's hit points are its defence, `damage` is how much of it was lost.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MilitaryCreation {
     pub name: String,
     pub level: u32,
     pub attack: u32,
     pub defence: u32,
+    #[serde(default)]
+    pub damage: u32,
+    #[serde(default)]
+    pub experience: u32,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MilitaryCreationTypes {
     Ship(MilitaryCreation),
     DefenceTurret(MilitaryCreation),
 }
 
+impl MilitaryCreationTypes {
+    pub fn get_creation(&self) -> &MilitaryCreation {
+        match self {
+            Self::Ship(creation) => creation,
+            Self::DefenceTurret(creation) => creation,
+        }
+    }
+
+    pub fn get_creation_mut(&mut self) -> &mut MilitaryCreation {
+        match self {
+            Self::Ship(creation) => creation,
+            Self::DefenceTurret(creation) => creation,
+        }
+    }
+}
+
 impl MilitaryCreation {
     pub fn new(name: String, level: u32, attack: u32, defence: u32) -> Self {
         Self {
@@ -19,10 +72,85 @@ impl MilitaryCreation {
             level,
             attack,
             defence,
+            damage: 0,
+            experience: 0,
+        }
+    }
+
+    pub fn max_hp(&self) -> u32 {
+        self.defence.max(1)
+    }
+
+    pub fn hp(&self) -> u32 {
+        self.max_hp().saturating_sub(self.damage)
+    }
+
+    /// Takes `damage` off the unit's hit points, returns what is left of it
+    /// once the unit is destroyed.
+    pub fn take_damage(&mut self, damage: u32) -> u32 {
+        let hp = self.hp();
+        self.damage += damage.min(hp);
+        damage.saturating_sub(hp)
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.hp() == 0
+    }
+
+    /// Restores up to `amount` hit points, returns how many were restored.
+    pub fn repair(&mut self, amount: u32) -> u32 {
+        let repaired = amount.min(self.damage);
+        self.damage -= repaired;
+        repaired
+    }
+
+    /// Adds `amount` experience and levels the unit up for as long as it has
+    /// enough, returns whether it gained a level.
+    pub fn gain_experience(&mut self, amount: u32) -> bool {
+        self.experience += amount;
+        let mut promoted = false;
+        while self.level < MAX_UNIT_LEVEL && self.experience >= self.level * EXPERIENCE_PER_LEVEL {
+            self.experience -= self.level * EXPERIENCE_PER_LEVEL;
+            self.level += 1;
+            self.attack += (self.attack * VETERANCY_BONUS_PERCENT / 100).max(1);
+            self.defence += (self.defence * VETERANCY_BONUS_PERCENT / 100).max(1);
+            promoted = true;
         }
+        promoted
     }
 }
 
+#[derive(Serialize, Deserialize)]
 pub struct Fleet {
+    /// 0 until the world hands out an id, see `GameWorld::assign_fleet_ids`
+    #[serde(default)]
+    pub id: FleetId,
     pub ships: Vec<MilitaryCreationTypes>,
+    #[serde(default)]
+    pub owner: Option<PlayerId>,
+    #[serde(default)]
+    pub position: Position,
+    #[serde(default = "default_fleet_speed")]
+    pub speed: u32,
+}
+
+impl Fleet {
+    pub fn new(owner: Option<PlayerId>) -> Self {
+        Self {
+            id: 0,
+            ships: vec![],
+            owner,
+            position: Position::default(),
+            speed: DEFAULT_FLEET_SPEED,
+        }
+    }
+
+    /// How many ships of each kind the fleet has, by name.
+    pub fn unit_counts(&self) -> BTreeMap<String, u32> {
+        let mut counts = BTreeMap::new();
+        for unit in &self.ships {
+            *counts.entry(unit.get_creation().name.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
 }
@@ -1,8 +1,35 @@
 pub mod build_tick_handler;
 pub mod buildings;
+pub mod capture;
+pub mod catalog;
+pub mod combat;
+pub mod construction;
+pub mod construction_tick_handler;
+pub mod deposit;
+pub mod diplomacy;
+pub mod diplomacy_tick_handler;
+pub mod error;
+pub mod fleet_movement_handler;
+pub mod galaxy;
 pub mod game_world;
+pub mod ledger;
+pub mod market;
+pub mod market_tick_handler;
 pub mod military;
+pub mod movement;
 pub mod planet;
+pub mod planet_trait;
+pub mod player;
+pub mod population;
+pub mod population_tick_handler;
 pub mod position;
+pub mod repair_tick_handler;
+pub mod research;
+pub mod research_tick_handler;
 pub mod resource;
+pub mod resource_registry;
+pub mod save;
+pub mod siege;
 pub mod tickable;
+pub mod trade;
+pub mod trade_tick_handler;
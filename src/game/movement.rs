@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use super::military::Fleet;
+use super::position::Position;
+
+/// Ticks a fleet with `speed` needs to travel between two positions, at least one.
+pub fn travel_ticks(from: &Position, to: &Position, speed: u32) -> u32 {
+    let ticks = (from.distance(to) / speed.max(1) as f64).ceil() as u32;
+    ticks.max(1)
+}
+
+/// A fleet that left its planet and is on its way to `destination`.
+#[derive(Serialize, Deserialize)]
+pub struct FleetMovement {
+    pub fleet_name: String,
+    pub fleet: Fleet,
+    pub origin: String,
+    pub destination: String,
+    pub from: Position,
+    pub to: Position,
+    pub total_ticks: u32,
+    pub ticks_elapsed: u32,
+}
+
+impl FleetMovement {
+    /// The fleet leaves from `from`, the position of the origin planet, whatever
+    /// position it had before.
+    pub fn new(
+        fleet_name: String,
+        mut fleet: Fleet,
+        origin: String,
+        destination: String,
+        from: Position,
+        to: Position,
+    ) -> Self {
+        fleet.position = from.clone();
+        let total_ticks = travel_ticks(&from, &to, fleet.speed);
+
+        Self {
+            fleet_name,
+            fleet,
+            origin,
+            destination,
+            from,
+            to,
+            total_ticks,
+            ticks_elapsed: 0,
+        }
+    }
+
+    pub fn ticks_remaining(&self) -> u32 {
+        self.total_ticks.saturating_sub(self.ticks_elapsed)
+    }
+
+    /// Moves the fleet one tick further along its path, returns true once it arrived.
+    pub fn advance(&mut self) -> bool {
+        self.ticks_elapsed += 1;
+        let progress = self.ticks_elapsed as f64 / self.total_ticks as f64;
+        self.fleet.position = self.from.lerp(&self.to, progress);
+        self.ticks_remaining() == 0
+    }
+}
@@ -1,99 +1,479 @@
-use super::buildings::Building;
-use super::military::Fleet;
-use super::military::MilitaryCreationTypes;
+use super::buildings::{Building, MAX_BUILDING_LEVEL};
+use super::catalog::BuildingCatalog;
+use super::construction::{Construction, ConstructionQueue};
+use super::deposit::Deposit;
+use super::error::GameError;
+use super::ledger::ResourceLedger;
+use super::military::{Fleet, FleetId};
+use super::military::{MilitaryCreation, MilitaryCreationTypes};
+use super::planet_trait::PlanetTrait;
+use super::player::PlayerId;
+use super::population::Population;
 use super::position::Position;
 use super::resource::Resource;
 use super::resource::ResourceType;
 use super::tickable::TickResult;
 use super::tickable::Tickable;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+pub const DEFAULT_BUILDING_LIMIT: usize = 20;
+/// How much of each resource a planet holds without storage buildings.
+pub const DEFAULT_STORAGE_CAPACITY: i32 = 1000;
+
+fn default_storage_capacity() -> i32 {
+    DEFAULT_STORAGE_CAPACITY
+}
+
+/// What came of a building's production besides the resources themselves.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Production {
+    /// deposits that ran out
+    pub depleted: Vec<ResourceType>,
+    /// output that didn't fit in storage
+    pub wasted: HashMap<ResourceType, i32>,
+}
+
+#[derive(Serialize, Deserialize)]
 pub struct Planet {
     pub name: String,
     pub buildings: Vec<Building>,
     pub military: HashMap<String, Fleet>,
     pub resources: HashMap<ResourceType, Resource>,
     pub position: Position,
+    pub building_limit: usize,
+    pub ledger: ResourceLedger,
+    pub construction_queue: ConstructionQueue,
+    #[serde(default)]
+    pub owner: Option<PlayerId>,
+    /// turrets fixed to the planet, they never join a fleet
+    #[serde(default)]
+    pub defences: Vec<MilitaryCreation>,
+    #[serde(default)]
+    pub traits: Vec<PlanetTrait>,
+    #[serde(default)]
+    pub deposits: HashMap<ResourceType, Deposit>,
+    #[serde(default)]
+    pub population: Population,
+    /// storage for each resource before storage buildings
+    #[serde(default = "default_storage_capacity")]
+    pub base_storage: i32,
 }
 
 impl Planet {
     pub fn add_resource(
         resource_map: &mut HashMap<ResourceType, Resource>,
         resource_to_add: HashMap<ResourceType, Resource>,
-    ) {
+    ) -> Result<(), GameError> {
+        // validate every entry first so a missing resource leaves the map untouched
+        if let Some(resource_type) = resource_to_add
+            .keys()
+            .find(|resource_type| !resource_map.contains_key(resource_type))
+        {
+            return Err(GameError::UnknownResource(*resource_type));
+        }
+
         for resource in resource_to_add {
-            let planet_resource = match resource_map.get_mut(&resource.0) {
+            if let Some(planet_resource) = resource_map.get_mut(&resource.0) {
+                planet_resource.amount += resource.1.amount;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies a signed resource delta on behalf of `source` as a single transaction.
+    /// If any debit would take a resource below zero nothing is applied and the
+    /// missing amounts are returned. Credits aren't capped at the storage capacity,
+    /// only production is, so resources moved between planets are never lost.
+    pub fn apply_transaction(
+        &mut self,
+        source: &str,
+        delta: &HashMap<ResourceType, Resource>,
+    ) -> Result<(), GameError> {
+        let mut shortfall = HashMap::new();
+
+        for (resource_type, change) in delta {
+            let planet_resource = match self.resources.get(resource_type) {
                 Some(planet_resource) => planet_resource,
-                None => {
-                    panic!("No resource have been found in the planet for building the building.")
-                }
+                None => return Err(GameError::UnknownResource(*resource_type)),
             };
 
-            planet_resource.amount += resource.1.amount;
+            let remaining = planet_resource.amount + change.amount;
+            if change.amount < 0 && remaining < 0 {
+                shortfall.insert(*resource_type, -remaining);
+            }
+        }
+
+        if !shortfall.is_empty() {
+            return Err(GameError::InsufficientResources(shortfall));
+        }
+
+        for (resource_type, change) in delta {
+            if change.amount == 0 {
+                continue;
+            }
+            if let Some(planet_resource) = self.resources.get_mut(resource_type) {
+                planet_resource.amount += change.amount;
+                self.ledger.record(source, *resource_type, change.amount);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies what a building produces on behalf of `source`. The planet's
+    /// traits and the owner's research `bonuses` scale the produced amounts, its
+    /// deposits cap them and whatever doesn't fit in storage is wasted, costs
+    /// are applied as they are.
+    pub fn produce(
+        &mut self,
+        source: &str,
+        delta: &HashMap<ResourceType, Resource>,
+        bonuses: &HashMap<ResourceType, i32>,
+    ) -> Result<Production, GameError> {
+        let mut production = Production::default();
+        let output: HashMap<ResourceType, Resource> = delta
+            .iter()
+            .map(|(resource_type, change)| {
+                let mut change = *change;
+                if change.amount > 0 {
+                    let amount = self.modified_output(*resource_type, change.amount, bonuses);
+                    change.amount = amount.min(self.storage_room(*resource_type));
+                    if change.amount < amount {
+                        production
+                            .wasted
+                            .insert(*resource_type, amount - change.amount);
+                    }
+                }
+                (*resource_type, change)
+            })
+            .collect();
+        self.apply_transaction(source, &output)?;
+
+        for (resource_type, change) in output {
+            if change.amount <= 0 {
+                continue;
+            }
+            if let Some(deposit) = self.deposits.get_mut(&resource_type) {
+                deposit.extract(change.amount);
+                if deposit.is_depleted() {
+                    production.depleted.push(resource_type);
+                }
+            }
         }
+        Ok(production)
+    }
+
+    /// How much of `resource_type` the planet can hold.
+    pub fn storage_capacity(&self, resource_type: ResourceType) -> i32 {
+        let extra: i32 = self
+            .buildings
+            .iter()
+            .filter_map(|building| match building {
+                Building::StorageBuilding(storage) => storage
+                    .capacity()
+                    .get(&resource_type)
+                    .map(|capacity| capacity.amount),
+                _ => None,
+            })
+            .sum();
+        self.base_storage + extra
+    }
+
+    /// How much more of `resource_type` fits in storage.
+    pub fn storage_room(&self, resource_type: ResourceType) -> i32 {
+        let amount = self
+            .resources
+            .get(&resource_type)
+            .map_or(0, |resource| resource.amount);
+        (self.storage_capacity(resource_type) - amount).max(0)
+    }
+
+    /// `amount` of `resource_type` after the planet's traits, research `bonuses`
+    /// and deposits.
+    pub fn modified_output(
+        &self,
+        resource_type: ResourceType,
+        amount: i32,
+        bonuses: &HashMap<ResourceType, i32>,
+    ) -> i32 {
+        let percent: i32 = self
+            .traits
+            .iter()
+            .map(|planet_trait| planet_trait.output_percent(resource_type))
+            .sum::<i32>()
+            + bonuses.get(&resource_type).copied().unwrap_or_default();
+        let amount = (amount * (100 + percent) / 100).max(0);
+
+        match self.deposits.get(&resource_type) {
+            Some(deposit) => deposit.available(amount),
+            None => amount,
+        }
+    }
+
+    /// Which buildings have workers this tick, buildings are staffed in order
+    /// until the population runs out.
+    pub fn staffed_buildings(&self) -> Vec<bool> {
+        let mut available = self.population.size;
+        self.buildings
+            .iter()
+            .map(|building| {
+                let workers = building.get_building_data().workers;
+                if workers > available {
+                    return false;
+                }
+                available -= workers;
+                true
+            })
+            .collect()
     }
 
     pub fn create_fleet(fleet_map: &mut HashMap<String, Fleet>, name: String) {
-        fleet_map.insert(name, Fleet { ships: vec![] });
+        fleet_map.insert(name, Fleet::new(None));
+    }
+
+    pub fn authorize(&self, player: PlayerId) -> Result<(), GameError> {
+        if self.owner == Some(player) {
+            Ok(())
+        } else {
+            Err(GameError::NotOwner(self.name.clone()))
+        }
+    }
+
+    /// Hands the planet and every fleet stationed on it to `owner`.
+    pub fn set_owner(&mut self, owner: Option<PlayerId>) {
+        self.owner = owner;
+        for fleet in self.military.values_mut() {
+            fleet.owner = owner;
+        }
     }
 
     pub fn add_military(fleet: &mut Fleet, military: MilitaryCreationTypes) {
         fleet.ships.push(military);
     }
 
+    pub fn add_military_to_fleet(
+        &mut self,
+        fleet_name: &str,
+        military: MilitaryCreationTypes,
+    ) -> Result<(), GameError> {
+        if let MilitaryCreationTypes::DefenceTurret(turret) = military {
+            return Err(GameError::ImmobileUnit(turret.name));
+        }
+
+        match self.military.get_mut(fleet_name) {
+            Some(fleet) => {
+                Planet::add_military(fleet, military);
+                Ok(())
+            }
+            None => Err(GameError::UnknownFleet(fleet_name.to_string())),
+        }
+    }
+
+    /// Stations a newly built unit, turrets become planetary defences and
+    /// ships join the default fleet.
+    pub fn add_military_unit(&mut self, military: MilitaryCreationTypes) -> Result<(), GameError> {
+        self.add_military_unit_to(military, None)
+    }
+
+    /// Like `add_military_unit`, but ships join the `rally` fleet while it is
+    /// stationed here under the planet's owner.
+    pub fn add_military_unit_to(
+        &mut self,
+        military: MilitaryCreationTypes,
+        rally: Option<FleetId>,
+    ) -> Result<(), GameError> {
+        match military {
+            MilitaryCreationTypes::DefenceTurret(turret) => {
+                self.defences.push(turret);
+                Ok(())
+            }
+            ship => {
+                let rally = rally
+                    .and_then(|id| self.fleet_name_of(id))
+                    .filter(|name| self.military[name].owner == self.owner);
+                let fleet_name = match rally {
+                    Some(rally) => rally,
+                    None => {
+                        let fleet_name = self.default_fleet_name();
+                        // the default fleet may have been sent away, a new one forms at home
+                        if !self.military.contains_key(&fleet_name) {
+                            let mut fleet = Fleet::new(self.owner);
+                            fleet.position = self.position.clone();
+                            self.military.insert(fleet_name.clone(), fleet);
+                        }
+                        fleet_name
+                    }
+                };
+                self.add_military_to_fleet(&fleet_name, ship)
+            }
+        }
+    }
+
+    /// Parks an arriving fleet in orbit, renaming it if the name is taken.
+    /// Returns the name the fleet is stationed under.
+    pub fn station_fleet(&mut self, name: &str, mut fleet: Fleet) -> String {
+        let mut fleet_name = name.to_string();
+        let mut suffix = 2;
+        while self.military.contains_key(&fleet_name) {
+            fleet_name = format!("{} ({})", name, suffix);
+            suffix += 1;
+        }
+
+        fleet.position = self.position.clone();
+        self.military.insert(fleet_name.clone(), fleet);
+        fleet_name
+    }
+
+    /// Summed hit points left on the planet's turrets.
+    pub fn defence_strength(&self) -> u32 {
+        self.defences.iter().map(|turret| turret.hp()).sum()
+    }
+
+    /// The name the fleet `id` is stationed under, if it is here.
+    pub fn fleet_name_of(&self, id: FleetId) -> Option<String> {
+        self.military
+            .iter()
+            .find(|(_, fleet)| fleet.id == id)
+            .map(|(name, _)| name.clone())
+    }
+
+    pub fn default_fleet_name(&self) -> String {
+        format!("{}{}", self.name, String::from(" fleet"))
+    }
+
     pub fn get_resource(&self, resource_type: ResourceType) -> Option<&Resource> {
-        return self.resources.get(&resource_type);
+        self.resources.get(&resource_type)
     }
 
-    pub fn build_building(&mut self, building: Building) -> Result<(), &str> {
+    pub fn build_building(&mut self, building: Building) -> Result<(), GameError> {
+        if self.buildings.len() + self.construction_queue.len() >= self.building_limit {
+            return Err(GameError::BuildingLimitReached(self.building_limit));
+        }
+
         let building_data = building.get_building_data();
+        building_data.check_cost(&self.resources)?;
 
         for resource in &building_data.resource_cost {
-            let planet_resource = match self.resources.get(&resource.0) {
+            let planet_resource = match self.resources.get_mut(resource.0) {
                 Some(planet_resource) => planet_resource,
-                None => return Err("Failed to get the required resource from the planet"),
+                None => return Err(GameError::UnknownResource(*resource.0)),
             };
 
-            if planet_resource.amount < resource.1.amount {
-                return Err("You don't have enough resources to build this building");
+            planet_resource.amount -= resource.1.amount;
+            if resource.1.amount != 0 {
+                self.ledger
+                    .record(&building_data.name, *resource.0, -resource.1.amount);
             }
         }
 
-        for resource in &building_data.resource_cost {
-            let planet_resource = match self.resources.get_mut(&resource.0) {
-                Some(planet_resource) => planet_resource,
-                None => return Err("Failed to get the required resource from the planet"),
+        if building_data.build_time == 0 {
+            self.buildings.push(building);
+        } else {
+            let resources_paid = building_data.resource_cost.clone();
+            self.construction_queue
+                .push(Construction::new(building, resources_paid));
+        }
+
+        Ok(())
+    }
+
+    /// Builds the catalog building `id` once all of its prerequisites stand on the planet.
+    pub fn build_from_catalog(
+        &mut self,
+        catalog: &BuildingCatalog,
+        id: &str,
+    ) -> Result<(), GameError> {
+        let template = match catalog.get(id) {
+            Some(template) => template,
+            None => return Err(GameError::UnknownTemplate(id.to_string())),
+        };
+
+        for prerequisite in &template.prerequisites {
+            let prerequisite = match catalog.get(prerequisite) {
+                Some(prerequisite) => prerequisite,
+                None => return Err(GameError::UnknownTemplate(prerequisite.clone())),
             };
 
-            planet_resource.amount -= resource.1.amount;
+            if !self.buildings.iter().any(|building| {
+                building
+                    .get_building_data()
+                    .is_template(&prerequisite.id, &prerequisite.name)
+            }) {
+                return Err(GameError::MissingPrerequisite(prerequisite.name.clone()));
+            }
         }
 
-        self.buildings.push(building);
+        self.build_building(template.create())
+    }
+
+    /// Removes a queued construction and refunds part of what was paid for it.
+    pub fn cancel_construction(&mut self, index: usize) -> Result<(), GameError> {
+        let construction = self.construction_queue.cancel(index)?;
+        let name = construction.building.get_building_data().name.clone();
+        self.apply_transaction(&name, &construction.refund())
+    }
+
+    pub fn reorder_construction(&mut self, from: usize, to: usize) -> Result<(), GameError> {
+        self.construction_queue.reorder(from, to)
+    }
+
+    /// Raises the building at `index` by one level, paying `BuildingData::upgrade_cost`.
+    pub fn upgrade_building(&mut self, index: usize) -> Result<(), GameError> {
+        let building_data = match self.buildings.get(index) {
+            Some(building) => building.get_building_data(),
+            None => return Err(GameError::UnknownBuilding(index)),
+        };
+
+        if building_data.level >= MAX_BUILDING_LEVEL {
+            return Err(GameError::MaxLevelReached(
+                building_data.name.clone(),
+                MAX_BUILDING_LEVEL,
+            ));
+        }
+
+        let name = building_data.name.clone();
+        let cost: HashMap<ResourceType, Resource> = building_data
+            .upgrade_cost()
+            .into_iter()
+            .map(|(resource_type, resource)| {
+                (
+                    resource_type,
+                    Resource {
+                        resource_type,
+                        amount: -resource.amount,
+                    },
+                )
+            })
+            .collect();
+        self.apply_transaction(&name, &cost)?;
+
+        self.buildings[index].get_building_data_mut().level += 1;
 
         Ok(())
     }
 
     pub fn new(name: String, pos_x: u32, pos_y: u32) -> Self {
-        let mut resources = HashMap::new();
-        let credit_resource = Resource {
-            resource_type: ResourceType::Credits,
-            amount: 0,
-        };
-        let food_resource = Resource {
-            resource_type: ResourceType::Food,
-            amount: 0,
-        };
-        let mineral_resource = Resource {
-            resource_type: ResourceType::Minerals,
-            amount: 0,
-        };
-        resources.insert(ResourceType::Credits, credit_resource);
-        resources.insert(ResourceType::Food, food_resource);
-        resources.insert(ResourceType::Minerals, mineral_resource);
+        // configured resource types are added by `ResourceRegistry::init_planet`
+        let resources = ResourceType::BUILT_IN
+            .into_iter()
+            .map(|resource_type| {
+                (
+                    resource_type,
+                    Resource {
+                        resource_type,
+                        amount: 0,
+                    },
+                )
+            })
+            .collect();
 
         let mut original_fleet = HashMap::new();
         let fleet_name = format!("{}{}", name, String::from(" fleet"));
-        original_fleet.insert(fleet_name, Fleet { ships: vec![] });
+        let mut fleet = Fleet::new(None);
+        fleet.position = Position { x: pos_x, y: pos_y };
+        original_fleet.insert(fleet_name, fleet);
 
         Self {
             name,
@@ -101,12 +481,21 @@ impl Planet {
             military: original_fleet,
             resources,
             position: Position { x: pos_x, y: pos_y },
+            building_limit: DEFAULT_BUILDING_LIMIT,
+            ledger: ResourceLedger::default(),
+            construction_queue: ConstructionQueue::default(),
+            owner: None,
+            defences: vec![],
+            traits: vec![],
+            deposits: HashMap::new(),
+            population: Population::default(),
+            base_storage: DEFAULT_STORAGE_CAPACITY,
         }
     }
 }
 
 impl Tickable for Planet {
-    fn tick(&mut self) -> TickResult {
-        TickResult::None
+    fn tick(&mut self) -> Vec<TickResult> {
+        vec![]
     }
 }
@@ -0,0 +1,49 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::resource::ResourceType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanetTrait {
+    Volcanic,
+    Ocean,
+    Barren,
+    GasGiant,
+}
+
+impl PlanetTrait {
+    pub const ALL: [PlanetTrait; 4] = [
+        PlanetTrait::Volcanic,
+        PlanetTrait::Ocean,
+        PlanetTrait::Barren,
+        PlanetTrait::GasGiant,
+    ];
+
+    /// Percent added to (or taken from) what buildings produce of `resource_type`.
+    pub fn output_percent(&self, resource_type: ResourceType) -> i32 {
+        match (self, resource_type) {
+            (Self::Volcanic, ResourceType::Minerals) => 50,
+            (Self::Volcanic, ResourceType::Food) => -50,
+            (Self::Ocean, ResourceType::Food) => 50,
+            (Self::Ocean, ResourceType::Minerals) => -25,
+            (Self::Barren, ResourceType::Minerals) => 25,
+            (Self::Barren, ResourceType::Food) => -75,
+            (Self::GasGiant, ResourceType::Credits) => 50,
+            (Self::GasGiant, ResourceType::Minerals) => -50,
+            _ => 0,
+        }
+    }
+}
+
+impl fmt::Display for PlanetTrait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Volcanic => write!(f, "volcanic"),
+            Self::Ocean => write!(f, "ocean"),
+            Self::Barren => write!(f, "barren"),
+            Self::GasGiant => write!(f, "gas giant"),
+        }
+    }
+}
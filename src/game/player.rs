@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+use super::research::Research;
+
+/// The external id of a player, the Discord user snowflake.
+pub type PlayerId = u64;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub id: PlayerId,
+    pub name: String,
+    #[serde(default)]
+    pub research: Research,
+}
+
+impl Player {
+    pub fn new(id: PlayerId, name: String) -> Self {
+        Self {
+            id,
+            name,
+            research: Research::default(),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_POPULATION_CAPACITY: u32 = 50;
+/// Food eaten by every inhabitant each tick.
+pub const FOOD_PER_INHABITANT: i32 = 1;
+/// Growth per tick while there is food to spare, at least one inhabitant,
+/// so an empty planet gets its first settlers too.
+pub const POPULATION_GROWTH_PERCENT: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Population {
+    pub size: u32,
+    pub capacity: u32,
+}
+
+impl Default for Population {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            capacity: DEFAULT_POPULATION_CAPACITY,
+        }
+    }
+}
+
+impl Population {
+    pub fn food_needed(&self) -> i32 {
+        self.size as i32 * FOOD_PER_INHABITANT
+    }
+
+    /// How many inhabitants are added when the population grows this tick.
+    pub fn growth(&self) -> u32 {
+        (self.size * POPULATION_GROWTH_PERCENT / 100)
+            .max(1)
+            .min(self.capacity.saturating_sub(self.size))
+    }
+}
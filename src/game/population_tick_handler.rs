@@ -0,0 +1,59 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    planet::Planet,
+    population::FOOD_PER_INHABITANT,
+    resource::{Resource, ResourceType},
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct PopulationTickRequest<'a> {
+    pub planet: &'a mut Planet,
+}
+
+impl RequestHandler<PopulationTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: PopulationTickRequest) {
+        let planet = req.planet;
+        // nobody settles a planet without an owner
+        if planet.population.size == 0 && planet.owner.is_none() {
+            return;
+        }
+
+        let food = planet
+            .get_resource(ResourceType::Food)
+            .map_or(0, |food| food.amount);
+        let needed = planet.population.food_needed();
+        // whoever can't be fed this tick starves, otherwise leftover food lets the population grow
+        let fed = (food.min(needed) / FOOD_PER_INHABITANT) as u32;
+        let eaten = fed as i32 * FOOD_PER_INHABITANT;
+
+        let mut meal = HashMap::new();
+        meal.insert(
+            ResourceType::Food,
+            Resource {
+                resource_type: ResourceType::Food,
+                amount: -eaten,
+            },
+        );
+        if let Err(err) = planet.apply_transaction("Population", &meal) {
+            self.publish(TickEvent::new([planet.owner], TickResult::Error(err)));
+            return;
+        }
+
+        let starved = planet.population.size - fed;
+        if starved > 0 {
+            planet.population.size = fed;
+            self.publish(TickEvent::new(
+                [planet.owner],
+                TickResult::Notification(format!(
+                    "{} inhabitants of {} starved",
+                    starved, planet.name
+                )),
+            ));
+        } else if food - eaten >= planet.population.food_needed().max(FOOD_PER_INHABITANT) {
+            planet.population.size += planet.population.growth();
+        }
+    }
+}
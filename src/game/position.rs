@@ -1,5 +1,39 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
     pub x: u32,
     pub y: u32,
 }
+
+impl Position {
+    pub fn distance(&self, other: &Position) -> f64 {
+        let dx = self.x as f64 - other.x as f64;
+        let dy = self.y as f64 - other.y as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+
+    /// Distance to the closest point of the segment from `start` to `end`.
+    pub fn distance_to_segment(&self, start: &Position, end: &Position) -> f64 {
+        let (x, y) = (self.x as f64, self.y as f64);
+        let (start_x, start_y) = (start.x as f64, start.y as f64);
+        let (dx, dy) = (end.x as f64 - start_x, end.y as f64 - start_y);
+        let length = dx * dx + dy * dy;
+        if length == 0.0 {
+            return self.distance(start);
+        }
+
+        let t = (((x - start_x) * dx + (y - start_y) * dy) / length).clamp(0.0, 1.0);
+        let (closest_x, closest_y) = (start_x + t * dx, start_y + t * dy);
+        ((x - closest_x).powi(2) + (y - closest_y).powi(2)).sqrt()
+    }
+
+    /// The point `progress` (0.0 to 1.0) of the way from `self` to `other`.
+    pub fn lerp(&self, other: &Position, progress: f64) -> Position {
+        let progress = progress.clamp(0.0, 1.0);
+        Position {
+            x: (self.x as f64 + (other.x as f64 - self.x as f64) * progress).round() as u32,
+            y: (self.y as f64 + (other.y as f64 - self.y as f64) * progress).round() as u32,
+        }
+    }
+}
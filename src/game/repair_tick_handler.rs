@@ -0,0 +1,73 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    diplomacy::{Diplomacy, Relation, TreatyKind},
+    military::{REPAIR_COST_PER_HP, REPAIR_PER_TICK},
+    planet::Planet,
+    player::PlayerId,
+    resource::{Resource, ResourceType},
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct RepairTickRequest<'a> {
+    pub planet: &'a mut Planet,
+    pub diplomacy: &'a Diplomacy,
+}
+
+impl RequestHandler<RepairTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: RepairTickRequest) {
+        let planet = req.planet;
+        let owner = match planet.owner {
+            Some(owner) => owner,
+            None => return,
+        };
+
+        // the planet pays for the repairs of its own and its allies' units
+        // for as long as its minerals last
+        let minerals = planet
+            .get_resource(ResourceType::Minerals)
+            .map_or(0, |minerals| minerals.amount);
+        let mut budget = (minerals / REPAIR_COST_PER_HP).max(0) as u32;
+        let friendly = |fleet_owner: Option<PlayerId>| match fleet_owner {
+            Some(fleet_owner) => {
+                fleet_owner == owner
+                    || req.diplomacy.relation(fleet_owner, owner)
+                        == Relation::Treaty(TreatyKind::Alliance)
+            }
+            None => false,
+        };
+
+        let ships = planet
+            .military
+            .values_mut()
+            .filter(|fleet| friendly(fleet.owner))
+            .flat_map(|fleet| fleet.ships.iter_mut())
+            .map(|ship| ship.get_creation_mut());
+        let mut repaired = 0;
+        for unit in ships.chain(planet.defences.iter_mut()) {
+            if budget == 0 {
+                break;
+            }
+            let amount = unit.repair(REPAIR_PER_TICK.min(budget));
+            budget -= amount;
+            repaired += amount;
+        }
+        if repaired == 0 {
+            return;
+        }
+
+        let mut cost = HashMap::new();
+        cost.insert(
+            ResourceType::Minerals,
+            Resource {
+                resource_type: ResourceType::Minerals,
+                amount: -(repaired as i32 * REPAIR_COST_PER_HP),
+            },
+        );
+        if let Err(err) = planet.apply_transaction("Repairs", &cost) {
+            self.publish(TickEvent::new([planet.owner], TickResult::Error(err)));
+        }
+    }
+}
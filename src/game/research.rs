@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::catalog::BuildingCatalog;
+use super::error::GameError;
+use super::resource::ResourceType;
+use super::resource_registry::ResourceRegistry;
+
+/// Id of the configured resource research is paid with.
+pub const RESEARCH_RESOURCE: &str = "research";
+
+pub fn research_resource() -> ResourceType {
+    ResourceType::from_id(RESEARCH_RESOURCE)
+}
+
+/// A node of the tech tree as described in the research file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tech {
+    pub id: String,
+    pub name: String,
+    /// research points needed to finish it
+    pub cost: i32,
+    /// ids of techs that must be researched first
+    #[serde(default)]
+    pub prerequisites: Vec<String>,
+    /// ids of catalog buildings nobody can build without this tech
+    #[serde(default)]
+    pub buildings: Vec<String>,
+    /// ids of catalog blueprints nobody can refit to without this tech
+    #[serde(default)]
+    pub blueprints: Vec<String>,
+    /// percent added to the production of each resource on every planet of the player
+    #[serde(default)]
+    pub bonuses: HashMap<ResourceType, i32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TechStatus {
+    Researched,
+    /// research points put in so far
+    Researching(i32),
+    Available,
+    /// some prerequisites are missing
+    Locked,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResearchTree {
+    pub techs: Vec<Tech>,
+}
+
+impl ResearchTree {
+    pub fn from_toml_str(source: &str) -> Result<Self, GameError> {
+        let tree: Self =
+            toml::from_str(source).map_err(|err| GameError::InvalidResearch(err.to_string()))?;
+        tree.validate()?;
+        Ok(tree)
+    }
+
+    pub fn from_json_str(source: &str) -> Result<Self, GameError> {
+        let tree: Self = serde_json::from_str(source)
+            .map_err(|err| GameError::InvalidResearch(err.to_string()))?;
+        tree.validate()?;
+        Ok(tree)
+    }
+
+    /// Loads a `.toml` or `.json` research file.
+    pub fn load(path: &Path) -> Result<Self, GameError> {
+        let source =
+            fs::read_to_string(path).map_err(|err| GameError::InvalidResearch(err.to_string()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&source),
+            Some("json") => Self::from_json_str(&source),
+            _ => Err(GameError::InvalidResearch(format!(
+                "{} is neither a .toml nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Tech> {
+        self.techs.iter().find(|tech| tech.id == id)
+    }
+
+    /// The tech that unlocks the catalog building `id`, if it needs one.
+    pub fn unlocking_building(&self, id: &str) -> Option<&Tech> {
+        self.techs
+            .iter()
+            .find(|tech| tech.buildings.iter().any(|building| building == id))
+    }
+
+    /// The tech that unlocks the catalog blueprint `id`, if it needs one.
+    pub fn unlocking_blueprint(&self, id: &str) -> Option<&Tech> {
+        self.techs
+            .iter()
+            .find(|tech| tech.blueprints.iter().any(|blueprint| blueprint == id))
+    }
+
+    /// Fails if a tech unlocks a building or blueprint `catalog` doesn't have,
+    /// or gives a bonus to a resource type `registry` doesn't know.
+    pub fn check_against(
+        &self,
+        catalog: &BuildingCatalog,
+        registry: &ResourceRegistry,
+    ) -> Result<(), GameError> {
+        let resource_types = registry.resource_types();
+        for tech in &self.techs {
+            if let Some(missing) = tech
+                .buildings
+                .iter()
+                .find(|building| catalog.get(building).is_none())
+            {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech \"{}\" unlocks unknown building \"{}\"",
+                    tech.id, missing
+                )));
+            }
+            if let Some(missing) = tech
+                .blueprints
+                .iter()
+                .find(|blueprint| catalog.get_blueprint(blueprint).is_none())
+            {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech \"{}\" unlocks unknown blueprint \"{}\"",
+                    tech.id, missing
+                )));
+            }
+            if let Some(resource_type) = tech
+                .bonuses
+                .keys()
+                .find(|resource_type| !resource_types.contains(resource_type))
+            {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech \"{}\" gives a bonus to unknown resource \"{}\"",
+                    tech.id, resource_type
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        for (index, tech) in self.techs.iter().enumerate() {
+            if self.techs[..index].iter().any(|other| other.id == tech.id) {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech id \"{}\" is used more than once",
+                    tech.id
+                )));
+            }
+            if tech.cost <= 0 {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech \"{}\" must cost something",
+                    tech.id
+                )));
+            }
+            // prerequisites have to come first, which also rules out cycles
+            if let Some(missing) = tech.prerequisites.iter().find(|prerequisite| {
+                !self.techs[..index]
+                    .iter()
+                    .any(|other| &other.id == *prerequisite)
+            }) {
+                return Err(GameError::InvalidResearch(format!(
+                    "tech \"{}\" requires \"{}\" which isn't listed before it",
+                    tech.id, missing
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// What a player has researched and is researching.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Research {
+    pub researched: BTreeSet<String>,
+    pub current: Option<String>,
+    pub progress: i32,
+}
+
+impl Research {
+    pub fn has(&self, id: &str) -> bool {
+        self.researched.contains(id)
+    }
+
+    pub fn status(&self, tech: &Tech) -> TechStatus {
+        if self.has(&tech.id) {
+            TechStatus::Researched
+        } else if self.current.as_deref() == Some(tech.id.as_str()) {
+            TechStatus::Researching(self.progress)
+        } else if tech
+            .prerequisites
+            .iter()
+            .all(|prerequisite| self.has(prerequisite))
+        {
+            TechStatus::Available
+        } else {
+            TechStatus::Locked
+        }
+    }
+
+    /// Summed production bonuses of every researched tech.
+    pub fn bonuses(&self, tree: &ResearchTree) -> HashMap<ResourceType, i32> {
+        let mut bonuses = HashMap::new();
+        for tech in tree.techs.iter().filter(|tech| self.has(&tech.id)) {
+            for (resource_type, percent) in &tech.bonuses {
+                *bonuses.entry(*resource_type).or_insert(0) += percent;
+            }
+        }
+        bonuses
+    }
+}
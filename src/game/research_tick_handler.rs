@@ -0,0 +1,79 @@
+use mediator_sys::synchronous::basic::*;
+
+use std::collections::HashMap;
+
+use super::{
+    planet::Planet,
+    player::{Player, PlayerId},
+    research::{research_resource, ResearchTree},
+    resource::Resource,
+    tickable::{TickEvent, TickResult},
+};
+
+pub struct ResearchTickRequest<'a> {
+    pub players: &'a mut HashMap<PlayerId, Player>,
+    pub planets: &'a mut Vec<Planet>,
+    pub tree: &'a ResearchTree,
+}
+
+impl RequestHandler<ResearchTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: ResearchTickRequest) {
+        let research_type = research_resource();
+
+        for player in req.players.values_mut() {
+            let tech = match player
+                .research
+                .current
+                .as_ref()
+                .and_then(|id| req.tree.get(id))
+            {
+                Some(tech) => tech,
+                None => continue,
+            };
+
+            // research points are spent from every planet of the player until the tech is paid
+            for planet in req
+                .planets
+                .iter_mut()
+                .filter(|planet| planet.owner == Some(player.id))
+            {
+                let missing = tech.cost - player.research.progress;
+                let available = planet
+                    .get_resource(research_type)
+                    .map_or(0, |resource| resource.amount);
+                let spent = missing.min(available);
+                if spent <= 0 {
+                    continue;
+                }
+
+                let mut payment = HashMap::new();
+                payment.insert(
+                    research_type,
+                    Resource {
+                        resource_type: research_type,
+                        amount: -spent,
+                    },
+                );
+                match planet.apply_transaction(&tech.name, &payment) {
+                    Ok(()) => player.research.progress += spent,
+                    Err(err) => {
+                        self.publish(TickEvent::new([Some(player.id)], TickResult::Error(err)))
+                    }
+                }
+            }
+
+            if player.research.progress >= tech.cost {
+                player.research.researched.insert(tech.id.clone());
+                player.research.current = None;
+                player.research.progress = 0;
+                self.publish(TickEvent::new(
+                    [Some(player.id)],
+                    TickResult::Notification(format!(
+                        "{} finished researching {}",
+                        player.name, tech.name
+                    )),
+                ));
+            }
+        }
+    }
+}
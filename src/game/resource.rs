@@ -1,4 +1,12 @@
-#[derive(Debug, Clone, Copy)]
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::resource_registry::ResourceRegistry;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Resource {
     pub resource_type: ResourceType,
     pub amount: i32,
@@ -9,4 +17,122 @@ pub enum ResourceType {
     Minerals,
     Credits,
     Food,
+    /// A resource type defined in the resource config.
+    Custom(ResourceId),
+}
+
+impl ResourceType {
+    /// The types every planet has, whatever the resource config says.
+    pub const BUILT_IN: [ResourceType; 3] = [
+        ResourceType::Minerals,
+        ResourceType::Credits,
+        ResourceType::Food,
+    ];
+
+    /// The type `id`, registering it when it is new. Untrusted ids go through
+    /// `known` instead.
+    pub fn from_id(id: &str) -> Self {
+        Self::known(id).unwrap_or_else(|| Self::Custom(ResourceId::new(id)))
+    }
+
+    /// The type `id` if it is built in or was registered by a resource config.
+    pub fn known(id: &str) -> Option<Self> {
+        match id {
+            "Minerals" => Some(Self::Minerals),
+            "Credits" => Some(Self::Credits),
+            "Food" => Some(Self::Food),
+            _ => ResourceId::get(id).map(Self::Custom),
+        }
+    }
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::Minerals => "Minerals",
+            Self::Credits => "Credits",
+            Self::Food => "Food",
+            Self::Custom(id) => id.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for ResourceType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+// saved and configured as the plain id, so `{ Credits = 5, energy = 2 }` works.
+// Ids nobody registered are rejected, a typo mustn't become a new resource.
+impl Serialize for ResourceType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for ResourceType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Self::known(&id)
+            .ok_or_else(|| de::Error::custom(format!("unknown resource type \"{}\"", id)))
+    }
+}
+
+/// Id of a configured resource type. Ids are interned, there are only ever as
+/// many as the configs define, so `ResourceType` can stay `Copy`.
+#[derive(Debug, Eq, Hash, PartialEq, Clone, Copy)]
+pub struct ResourceId(&'static str);
+
+/// Every interned id, the ones of the default resource config from the start.
+fn interned_ids() -> MutexGuard<'static, HashSet<&'static str>> {
+    static IDS: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+
+    IDS.get_or_init(|| {
+        let defaults = ResourceRegistry::default()
+            .resources
+            .into_iter()
+            .map(|definition| &*Box::leak(definition.id.into_boxed_str()))
+            .collect();
+        Mutex::new(defaults)
+    })
+    .lock()
+    .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl ResourceId {
+    pub fn new(id: &str) -> Self {
+        let mut ids = interned_ids();
+        match ids.get(id) {
+            Some(interned) => Self(interned),
+            None => {
+                let interned: &'static str = Box::leak(id.to_string().into_boxed_str());
+                ids.insert(interned);
+                Self(interned)
+            }
+        }
+    }
+
+    /// The id if it was already interned.
+    pub fn get(id: &str) -> Option<Self> {
+        interned_ids().get(id).map(|interned| Self(interned))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+/// Builds a resource map from plain amounts.
+pub fn resource_map(amounts: &HashMap<ResourceType, i32>) -> HashMap<ResourceType, Resource> {
+    amounts
+        .iter()
+        .map(|(resource_type, amount)| {
+            (
+                *resource_type,
+                Resource {
+                    resource_type: *resource_type,
+                    amount: *amount,
+                },
+            )
+        })
+        .collect()
 }
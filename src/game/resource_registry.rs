@@ -0,0 +1,174 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::error::GameError;
+use super::planet::Planet;
+use super::research::RESEARCH_RESOURCE;
+use super::resource::{Resource, ResourceType};
+
+/// The most resource types a config may define.
+pub const MAX_RESOURCE_TYPES: usize = 100;
+
+/// A resource type as described in the resource config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceDefinition {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub emoji: String,
+}
+
+impl ResourceDefinition {
+    pub fn resource_type(&self) -> ResourceType {
+        ResourceType::from_id(&self.id)
+    }
+}
+
+/// Every resource type planets keep track of, in display order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceRegistry {
+    pub resources: Vec<ResourceDefinition>,
+}
+
+impl Default for ResourceRegistry {
+    fn default() -> Self {
+        let definition = |id: &str, name: &str, emoji: &str| ResourceDefinition {
+            id: id.to_string(),
+            name: name.to_string(),
+            emoji: emoji.to_string(),
+        };
+        Self {
+            resources: vec![
+                definition("Credits", "Credits", "💰"),
+                definition("Minerals", "Minerals", "💎"),
+                definition("Food", "Food", "🌾"),
+                definition(RESEARCH_RESOURCE, "Research", "🔬"),
+            ],
+        }
+    }
+}
+
+impl ResourceRegistry {
+    pub fn from_toml_str(source: &str) -> Result<Self, GameError> {
+        let registry: Self = toml::from_str(source)
+            .map_err(|err| GameError::InvalidResourceConfig(err.to_string()))?;
+        registry.register()?;
+        Ok(registry)
+    }
+
+    pub fn from_json_str(source: &str) -> Result<Self, GameError> {
+        let registry: Self = serde_json::from_str(source)
+            .map_err(|err| GameError::InvalidResourceConfig(err.to_string()))?;
+        registry.register()?;
+        Ok(registry)
+    }
+
+    /// Loads a `.toml` or `.json` resource config file.
+    pub fn load(path: &Path) -> Result<Self, GameError> {
+        let source = fs::read_to_string(path)
+            .map_err(|err| GameError::InvalidResourceConfig(err.to_string()))?;
+
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => Self::from_toml_str(&source),
+            Some("json") => Self::from_json_str(&source),
+            _ => Err(GameError::InvalidResourceConfig(format!(
+                "{} is neither a .toml nor a .json file",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn get(&self, resource_type: ResourceType) -> Option<&ResourceDefinition> {
+        self.resources
+            .iter()
+            .find(|definition| definition.id == resource_type.id())
+    }
+
+    /// The type whose id or display name is `name`, ignoring case.
+    pub fn find(&self, name: &str) -> Option<ResourceType> {
+        self.resource_types().into_iter().find(|resource_type| {
+            resource_type.id().eq_ignore_ascii_case(name)
+                || self
+                    .get(*resource_type)
+                    .is_some_and(|definition| definition.name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The built in types followed by the configured ones.
+    pub fn resource_types(&self) -> Vec<ResourceType> {
+        let mut resource_types = ResourceType::BUILT_IN.to_vec();
+        for definition in &self.resources {
+            let resource_type = definition.resource_type();
+            if !resource_types.contains(&resource_type) {
+                resource_types.push(resource_type);
+            }
+        }
+        resource_types
+    }
+
+    /// The emoji and display name of `resource_type`, its id if it isn't configured.
+    pub fn label(&self, resource_type: ResourceType) -> String {
+        match self.get(resource_type) {
+            Some(definition) if definition.emoji.is_empty() => definition.name.clone(),
+            Some(definition) => format!("{} {}", definition.emoji, definition.name),
+            None => resource_type.to_string(),
+        }
+    }
+
+    /// Where `resource_type` is listed, unconfigured types go last.
+    pub fn display_order(&self, resource_type: ResourceType) -> usize {
+        self.resources
+            .iter()
+            .position(|definition| definition.id == resource_type.id())
+            .unwrap_or(self.resources.len())
+    }
+
+    /// Adds every missing resource type to `planet` with an amount of 0.
+    pub fn init_planet(&self, planet: &mut Planet) {
+        for resource_type in self.resource_types() {
+            planet.resources.entry(resource_type).or_insert(Resource {
+                resource_type,
+                amount: 0,
+            });
+        }
+    }
+
+    /// Validates the config and registers its resource types, only registered
+    /// types can be loaded from catalogs and saves.
+    pub fn register(&self) -> Result<(), GameError> {
+        self.validate()?;
+        for definition in &self.resources {
+            definition.resource_type();
+        }
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        if self.resources.len() > MAX_RESOURCE_TYPES {
+            return Err(GameError::InvalidResourceConfig(format!(
+                "there can't be more than {} resource types",
+                MAX_RESOURCE_TYPES
+            )));
+        }
+        let mut seen = HashSet::new();
+        for definition in &self.resources {
+            if definition.id.is_empty() || definition.id.contains(char::is_whitespace) {
+                return Err(GameError::InvalidResourceConfig(format!(
+                    "\"{}\" is not a valid resource id",
+                    definition.id
+                )));
+            }
+            if !seen.insert(definition.id.as_str()) {
+                return Err(GameError::InvalidResourceConfig(format!(
+                    "resource id \"{}\" is used more than once",
+                    definition.id
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
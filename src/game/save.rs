@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::capture::CaptureRules;
+use super::catalog::BuildingCatalog;
+use super::diplomacy::Diplomacy;
+use super::error::GameError;
+use super::game_world::GameWorld;
+use super::market::Market;
+use super::military::FleetId;
+use super::movement::FleetMovement;
+use super::planet::Planet;
+use super::player::{Player, PlayerId};
+use super::research::ResearchTree;
+use super::resource_registry::ResourceRegistry;
+use super::trade::{Convoy, TradeRoute};
+
+/// Version written into every save file, bump it and add a migration when the
+/// saved state changes shape.
+pub const SAVE_VERSION: u32 = 1;
+
+/// Rewrites a save of version `n` into version `n + 1`.
+pub type Migration = fn(Value) -> Result<Value, GameError>;
+
+/// `MIGRATIONS[0]` migrates version 1 saves to version 2 and so on.
+const MIGRATIONS: &[Migration] = &[];
+
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == SAVE_VERSION);
+
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    planets: &'a [Planet],
+    catalog: &'a BuildingCatalog,
+    players: &'a HashMap<PlayerId, Player>,
+    movements: &'a [FleetMovement],
+    capture_rules: &'a CaptureRules,
+    resources: &'a ResourceRegistry,
+    research: &'a ResearchTree,
+    trade_routes: &'a [TradeRoute],
+    convoys: &'a [Convoy],
+    next_trade_route_id: u32,
+    market: &'a Market,
+    diplomacy: &'a Diplomacy,
+    next_fleet_id: FleetId,
+    admin: Option<PlayerId>,
+}
+
+#[derive(Deserialize)]
+struct SaveFile {
+    planets: Vec<Planet>,
+    #[serde(default)]
+    catalog: BuildingCatalog,
+    #[serde(default)]
+    players: HashMap<PlayerId, Player>,
+    #[serde(default)]
+    movements: Vec<FleetMovement>,
+    #[serde(default)]
+    capture_rules: CaptureRules,
+    #[serde(default)]
+    resources: ResourceRegistry,
+    #[serde(default)]
+    research: ResearchTree,
+    #[serde(default)]
+    trade_routes: Vec<TradeRoute>,
+    #[serde(default)]
+    convoys: Vec<Convoy>,
+    #[serde(default)]
+    next_trade_route_id: u32,
+    #[serde(default)]
+    market: Market,
+    #[serde(default)]
+    diplomacy: Diplomacy,
+    #[serde(default)]
+    next_fleet_id: FleetId,
+    #[serde(default)]
+    admin: Option<PlayerId>,
+}
+
+pub fn world_to_string(world: &GameWorld) -> Result<String, GameError> {
+    serde_json::to_string(&SaveFileRef {
+        version: SAVE_VERSION,
+        planets: &world.planets,
+        catalog: &world.catalog,
+        players: &world.players,
+        movements: &world.movements,
+        capture_rules: &world.capture_rules,
+        resources: &world.resources,
+        research: &world.research,
+        trade_routes: &world.trade_routes,
+        convoys: &world.convoys,
+        next_trade_route_id: world.next_trade_route_id,
+        market: &world.market,
+        diplomacy: &world.diplomacy,
+        next_fleet_id: world.next_fleet_id,
+        admin: world.admin,
+    })
+    .map_err(|err| GameError::InvalidSave(err.to_string()))
+}
+
+pub fn world_from_string(source: &str) -> Result<GameWorld, GameError> {
+    let save: Value =
+        serde_json::from_str(source).map_err(|err| GameError::InvalidSave(err.to_string()))?;
+    let save = migrate(save, MIGRATIONS)?;
+    // the save's resource types have to be known before anything using them is read
+    if let Some(resources) = save.get("resources") {
+        let resources: ResourceRegistry = serde_json::from_value(resources.clone())
+            .map_err(|err| GameError::InvalidSave(err.to_string()))?;
+        resources
+            .register()
+            .map_err(|err| GameError::InvalidSave(err.to_string()))?;
+    }
+    let save: SaveFile =
+        serde_json::from_value(save).map_err(|err| GameError::InvalidSave(err.to_string()))?;
+
+    let mut world = GameWorld::new(save.planets);
+    world.catalog = save.catalog;
+    world.players = save.players;
+    world.movements = save.movements;
+    world.capture_rules = save.capture_rules;
+    world.set_resources(save.resources);
+    world.research = save.research;
+    world.trade_routes = save.trade_routes;
+    world.convoys = save.convoys;
+    world.next_trade_route_id = save.next_trade_route_id.max(1);
+    world.market = save.market;
+    world.diplomacy = save.diplomacy;
+    // ids of disbanded fleets are never handed out again
+    world.next_fleet_id = world.next_fleet_id.max(save.next_fleet_id);
+    world.assign_fleet_ids();
+    // saves from before admins run by their first player
+    world.admin = save.admin.or_else(|| world.players.keys().min().copied());
+    Ok(world)
+}
+
+pub fn save_world(world: &GameWorld, path: &Path) -> Result<(), GameError> {
+    let save = world_to_string(world)?;
+    fs::write(path, save).map_err(|err| GameError::InvalidSave(err.to_string()))
+}
+
+pub fn load_world(path: &Path) -> Result<GameWorld, GameError> {
+    let source = fs::read_to_string(path).map_err(|err| GameError::InvalidSave(err.to_string()))?;
+    world_from_string(&source)
+}
+
+/// Runs every migration needed to bring `save` up to the latest version of `migrations`.
+pub fn migrate(mut save: Value, migrations: &[Migration]) -> Result<Value, GameError> {
+    let latest = migrations.len() as u64 + 1;
+    let mut version = match save.get("version").and_then(Value::as_u64) {
+        Some(version) => version,
+        None => return Err(GameError::InvalidSave(String::from("missing save version"))),
+    };
+
+    if version == 0 || version > latest {
+        return Err(GameError::UnsupportedSaveVersion(version));
+    }
+
+    while version < latest {
+        save = migrations[version as usize - 1](save)?;
+        version += 1;
+        save["version"] = Value::from(version);
+    }
+
+    Ok(save)
+}
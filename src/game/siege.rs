@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use super::combat::{resolve_battle, BattleReport};
+use super::diplomacy::Diplomacy;
+use super::military::{Fleet, MilitaryCreationTypes};
+use super::planet::Planet;
+use super::player::PlayerId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SiegeReport {
+    pub planet: String,
+    pub attacker: String,
+    #[serde(default)]
+    pub attacker_owner: Option<PlayerId>,
+    /// the owner of the planet when the siege began
+    #[serde(default)]
+    pub defender_owner: Option<PlayerId>,
+    pub battles: Vec<BattleReport>,
+    /// hit points of the turrets still standing after the battles
+    pub planet_defence: u32,
+    /// attack of what is left of the hostile fleet
+    pub siege_strength: u32,
+    pub can_capture: bool,
+}
+
+/// Fleets of the planet's owner and of players with a treaty with them don't attack it.
+pub fn is_hostile(planet: &Planet, fleet: &Fleet, diplomacy: &Diplomacy) -> bool {
+    diplomacy.is_hostile(fleet.owner, planet.owner)
+}
+
+/// A hostile fleet arriving at `planet` first fights every fleet of the planet's
+/// owner stationed there, then the planet's turrets. The planet can be captured
+/// once nothing defends it anymore and the survivors out-attack the remaining
+/// turret defence.
+pub fn resolve_siege(
+    planet: &mut Planet,
+    attacker_name: &str,
+    attacker: &mut Fleet,
+) -> SiegeReport {
+    let mut battles = vec![];
+    let defender_owner = planet.owner;
+
+    let mut defending_fleets: Vec<String> = planet
+        .military
+        .iter()
+        .filter(|(_, fleet)| fleet.owner == planet.owner && !fleet.ships.is_empty())
+        .map(|(name, _)| name.clone())
+        .collect();
+    defending_fleets.sort();
+
+    for fleet_name in defending_fleets {
+        if attacker.ships.is_empty() {
+            break;
+        }
+        if let Some(defender) = planet.military.get_mut(&fleet_name) {
+            battles.push(resolve_battle(
+                attacker_name,
+                attacker,
+                &fleet_name,
+                defender,
+            ));
+        }
+    }
+
+    if !attacker.ships.is_empty() && !planet.defences.is_empty() {
+        let mut turrets = Fleet::new(planet.owner);
+        turrets.ships = planet
+            .defences
+            .drain(..)
+            .map(MilitaryCreationTypes::DefenceTurret)
+            .collect();
+
+        let defences_name = format!("{} defences", planet.name);
+        battles.push(resolve_battle(
+            attacker_name,
+            attacker,
+            &defences_name,
+            &mut turrets,
+        ));
+
+        planet.defences = turrets
+            .ships
+            .into_iter()
+            .map(|turret| turret.get_creation().clone())
+            .collect();
+    }
+
+    let defended = planet
+        .military
+        .values()
+        .any(|fleet| fleet.owner == planet.owner && !fleet.ships.is_empty());
+    let planet_defence = planet.defence_strength();
+    let siege_strength: u32 = attacker
+        .ships
+        .iter()
+        .map(|unit| unit.get_creation().attack)
+        .sum();
+
+    SiegeReport {
+        planet: planet.name.clone(),
+        attacker: attacker_name.to_string(),
+        attacker_owner: attacker.owner,
+        defender_owner,
+        battles,
+        planet_defence,
+        siege_strength,
+        can_capture: !defended && siege_strength > planet_defence,
+    }
+}
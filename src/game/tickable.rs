@@ -1,15 +1,44 @@
 use std::collections::HashMap;
 
+use super::capture::CaptureReport;
+use super::error::GameError;
 use super::military::MilitaryCreationTypes;
+use super::player::PlayerId;
 use super::resource::{Resource, ResourceType};
+use super::siege::SiegeReport;
 
+/// A tick returns every effect it produced, they are applied in order.
 pub trait Tickable {
-    fn tick(&mut self) -> TickResult;
+    fn tick(&mut self) -> Vec<TickResult>;
 }
 
 #[derive(Debug, Clone)]
 pub enum TickResult {
     ResourceResult(HashMap<ResourceType, Resource>),
     MilitaryBuildResult(MilitaryCreationTypes),
-    None,
+    /// A military building made no progress because its upkeep couldn't be paid.
+    MilitaryStalled(String, HashMap<ResourceType, i32>),
+    Error(GameError),
+    /// A message for the owner of the planet, published as is.
+    Notification(String),
+    /// A hostile fleet arrived at a planet and fought its defenders.
+    Siege(SiegeReport),
+    Captured(CaptureReport),
+}
+
+/// A tick result and the players it is meant for, each of them collects it once.
+#[derive(Debug, Clone)]
+pub struct TickEvent {
+    pub recipients: Vec<PlayerId>,
+    pub result: TickResult,
+}
+
+impl TickEvent {
+    /// An event for every owner in `recipients`, unowned planets and fleets hear nothing.
+    pub fn new(recipients: impl IntoIterator<Item = Option<PlayerId>>, result: TickResult) -> Self {
+        let mut recipients: Vec<PlayerId> = recipients.into_iter().flatten().collect();
+        recipients.sort();
+        recipients.dedup();
+        Self { recipients, result }
+    }
 }
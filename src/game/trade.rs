@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::movement::travel_ticks;
+use super::player::PlayerId;
+use super::position::Position;
+use super::resource::ResourceType;
+
+/// Distance units a convoy covers per tick.
+pub const CONVOY_SPEED: u32 = 4;
+/// Hostile fleets closer than this to a convoy's path intercept it.
+pub const INTERCEPT_RANGE: f64 = 3.0;
+
+/// Ships `cargo` from `origin` to `destination` every `interval` ticks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeRoute {
+    pub id: u32,
+    pub owner: PlayerId,
+    pub origin: String,
+    pub destination: String,
+    pub cargo: HashMap<ResourceType, i32>,
+    pub interval: u32,
+    pub ticks_until_departure: u32,
+}
+
+/// A shipment of a trade route on its way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Convoy {
+    pub route: u32,
+    pub owner: PlayerId,
+    pub cargo: HashMap<ResourceType, i32>,
+    pub origin: String,
+    pub destination: String,
+    pub from: Position,
+    pub to: Position,
+    pub position: Position,
+    pub total_ticks: u32,
+    pub ticks_elapsed: u32,
+}
+
+impl Convoy {
+    pub fn new(route: &TradeRoute, from: Position, to: Position) -> Self {
+        let total_ticks = travel_ticks(&from, &to, CONVOY_SPEED);
+        Self {
+            route: route.id,
+            owner: route.owner,
+            cargo: route.cargo.clone(),
+            origin: route.origin.clone(),
+            destination: route.destination.clone(),
+            position: from.clone(),
+            from,
+            to,
+            total_ticks,
+            ticks_elapsed: 0,
+        }
+    }
+
+    pub fn ticks_remaining(&self) -> u32 {
+        self.total_ticks.saturating_sub(self.ticks_elapsed)
+    }
+
+    /// Moves the convoy one tick further along its path, returns true once it arrived.
+    pub fn advance(&mut self) -> bool {
+        self.ticks_elapsed += 1;
+        let progress = self.ticks_elapsed as f64 / self.total_ticks as f64;
+        self.position = self.from.lerp(&self.to, progress);
+        self.ticks_remaining() == 0
+    }
+}
@@ -0,0 +1,176 @@
+use mediator_sys::synchronous::basic::*;
+
+use super::{
+    diplomacy::Diplomacy,
+    error::GameError,
+    movement::FleetMovement,
+    planet::Planet,
+    resource::resource_map,
+    tickable::{TickEvent, TickResult},
+    trade::{Convoy, TradeRoute, INTERCEPT_RANGE},
+};
+
+pub struct TradeTickRequest<'a> {
+    pub planets: &'a mut Vec<Planet>,
+    pub routes: &'a mut Vec<TradeRoute>,
+    pub convoys: &'a mut Vec<Convoy>,
+    pub movements: &'a [FleetMovement],
+    pub diplomacy: &'a Diplomacy,
+}
+
+impl RequestHandler<TradeTickRequest<'_>, TickEvent> for BasicMediator<TickEvent> {
+    fn handle(&self, req: TradeTickRequest) {
+        // a route closes once its owner lost the planet it ships from
+        req.routes.retain(|route| {
+            let open = req
+                .planets
+                .iter()
+                .any(|planet| planet.name == route.origin && planet.owner == Some(route.owner));
+            if !open {
+                self.publish(TickEvent::new(
+                    [Some(route.owner)],
+                    TickResult::Notification(format!(
+                        "The trade route from {} to {} has been closed",
+                        route.origin, route.destination
+                    )),
+                ));
+            }
+            open
+        });
+
+        for route in req.routes.iter_mut() {
+            route.ticks_until_departure = route.ticks_until_departure.saturating_sub(1);
+            if route.ticks_until_departure > 0 {
+                continue;
+            }
+            route.ticks_until_departure = route.interval;
+
+            let to = match req
+                .planets
+                .iter()
+                .find(|planet| planet.name == route.destination)
+            {
+                Some(destination) => destination.position.clone(),
+                None => {
+                    self.publish(TickEvent::new(
+                        [Some(route.owner)],
+                        TickResult::Error(GameError::UnknownPlanet(route.destination.clone())),
+                    ));
+                    continue;
+                }
+            };
+            let origin = match req
+                .planets
+                .iter_mut()
+                .find(|planet| planet.name == route.origin)
+            {
+                Some(origin) => origin,
+                None => {
+                    self.publish(TickEvent::new(
+                        [Some(route.owner)],
+                        TickResult::Error(GameError::UnknownPlanet(route.origin.clone())),
+                    ));
+                    continue;
+                }
+            };
+            let load = route
+                .cargo
+                .iter()
+                .map(|(resource_type, amount)| (*resource_type, -amount))
+                .collect();
+            let source = format!("Trade route to {}", route.destination);
+            match origin.apply_transaction(&source, &resource_map(&load)) {
+                Ok(()) => req
+                    .convoys
+                    .push(Convoy::new(route, origin.position.clone(), to)),
+                Err(err) => self.publish(TickEvent::new(
+                    [Some(route.owner)],
+                    TickResult::Notification(format!(
+                        "No convoy left {} for {}: {}",
+                        route.origin, route.destination, err
+                    )),
+                )),
+            }
+        }
+
+        let convoys = std::mem::take(req.convoys);
+        for mut convoy in convoys {
+            let previous = convoy.position.clone();
+            let arrived = convoy.advance();
+            let recipient = req
+                .planets
+                .iter()
+                .find(|planet| planet.name == convoy.destination)
+                .and_then(|planet| planet.owner);
+
+            // any fleet close to the path of someone at odds with both trading players
+            let hostile = |owner: Option<_>| {
+                owner.is_some()
+                    && req.diplomacy.is_hostile(owner, Some(convoy.owner))
+                    && (recipient.is_none() || req.diplomacy.is_hostile(owner, recipient))
+            };
+            let stationed = req.planets.iter().flat_map(|planet| {
+                planet
+                    .military
+                    .iter()
+                    .map(move |(name, fleet)| (name, fleet, &planet.position))
+            });
+            let moving = req.movements.iter().map(|movement| {
+                (
+                    &movement.fleet_name,
+                    &movement.fleet,
+                    &movement.fleet.position,
+                )
+            });
+            let interceptor = stationed.chain(moving).find(|(_, fleet, position)| {
+                hostile(fleet.owner)
+                    && !fleet.ships.is_empty()
+                    && position.distance_to_segment(&previous, &convoy.position) <= INTERCEPT_RANGE
+            });
+
+            if let Some((fleet_name, fleet, _)) = interceptor {
+                self.publish(TickEvent::new(
+                    [Some(convoy.owner), recipient, fleet.owner],
+                    TickResult::Notification(format!(
+                        "The convoy from {} to {} was intercepted by {}",
+                        convoy.origin, convoy.destination, fleet_name
+                    )),
+                ));
+                continue;
+            }
+
+            if !arrived {
+                req.convoys.push(convoy);
+                continue;
+            }
+
+            let destination = match req
+                .planets
+                .iter_mut()
+                .find(|planet| planet.name == convoy.destination)
+            {
+                Some(destination) => destination,
+                None => {
+                    self.publish(TickEvent::new(
+                        [Some(convoy.owner)],
+                        TickResult::Error(GameError::UnknownPlanet(convoy.destination)),
+                    ));
+                    continue;
+                }
+            };
+            let source = format!("Convoy from {}", convoy.origin);
+            match destination.apply_transaction(&source, &resource_map(&convoy.cargo)) {
+                Ok(()) => self.publish(TickEvent::new(
+                    [Some(convoy.owner), recipient],
+                    TickResult::Notification(format!(
+                        "The convoy from {} arrived at {}",
+                        convoy.origin, convoy.destination
+                    )),
+                )),
+                Err(err) => {
+                    self.publish(TickEvent::new([Some(convoy.owner)], TickResult::Error(err)))
+                }
+            }
+        }
+    }
+}
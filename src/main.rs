@@ -1,64 +1,114 @@
-mod game;
-use std::collections::HashMap;
+//! Plays the game in the terminal. Connecting to Discord is out of scope for
+//! this crate, a bot wraps its Discord client in a `Gateway` and feeds every
+//! message to `handle_message` the same way the console does here.
 
-use game::{
-    build_tick_handler::BuildingTickRequest,
-    buildings::{Building, BuildingData, ResourceBuilding},
-    planet::Planet,
-    resource::{Resource, ResourceType},
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use space_game_discord::commands::{
+    gateway::{Gateway, IncomingMessage, OutgoingMessage},
+    handler::handle_message,
 };
-use mediator_sys::{
-    builder::{BuilderFlow, BuilderInternal},
-    synchronous::basic::{BasicMediator, SyncMediatorInternalHandle},
+use space_game_discord::game::{
+    catalog::BuildingCatalog,
+    galaxy::GalaxyConfig,
+    game_world::GameWorld,
+    research::ResearchTree,
+    resource_registry::ResourceRegistry,
+    save::{load_world, save_world},
 };
 
-use crate::game::tickable::TickResult;
+/// Where the game is kept between runs, it is saved after every command.
+const SAVE_PATH: &str = "world.json";
 
-fn main() {
-    let mut planet_1 = Planet::new(String::from("planet_1"), 0, 0);
+/// Plays the game from the terminal as a single local player.
+struct ConsoleGateway {
+    lines: io::Lines<io::StdinLock<'static>>,
+}
 
-    let mut create_type: HashMap<ResourceType, Resource> = HashMap::new();
-    create_type.insert(
-        ResourceType::Minerals,
-        Resource {
-            resource_type: ResourceType::Minerals,
-            amount: 10,
-        },
-    );
+impl Gateway for ConsoleGateway {
+    fn receive(&mut self) -> Option<IncomingMessage> {
+        print!("> ");
+        io::stdout().flush().ok();
 
-    let mut resource_costs: HashMap<ResourceType, Resource> = HashMap::new();
-    resource_costs.insert(
-        ResourceType::Credits,
-        Resource {
-            resource_type: ResourceType::Credits,
-            amount: 0,
-        },
-    );
-    let resource_building: ResourceBuilding = ResourceBuilding {
-        building: BuildingData {
-            name: String::from("ResourceBuildingName"),
-            resource_cost: resource_costs,
-            level: 1,
-        },
-        create_type,
-    };
+        let content = self.lines.next()?.ok()?;
+        Some(IncomingMessage {
+            channel: 0,
+            author: 1,
+            author_name: String::from("local player"),
+            content,
+        })
+    }
+
+    fn send(&mut self, message: OutgoingMessage) {
+        println!("{}", message.content);
+    }
+}
 
-    planet_1
-        .build_building(Building::ResourceBuilding(resource_building))
-        .unwrap();
+/// A fresh galaxy set up from the files in `assets`.
+fn new_world() -> Option<GameWorld> {
+    let mut world = match GameWorld::from_galaxy(&GalaxyConfig::new(0, 12)) {
+        Ok(world) => world,
+        Err(err) => {
+            eprintln!("{}", err);
+            return None;
+        }
+    };
+    match ResourceRegistry::load(Path::new("assets/resources.toml")) {
+        Ok(registry) => world.set_resources(registry),
+        Err(err) => eprintln!("{}", err),
+    }
+    match BuildingCatalog::load(Path::new("assets/buildings.toml")) {
+        Ok(catalog) => {
+            if let Err(err) = world.set_catalog(catalog) {
+                eprintln!("{}", err);
+            }
+        }
+        Err(err) => eprintln!("{}", err),
+    }
 
-    println!(
-        "{:?}",
-        planet_1.get_resource(ResourceType::Minerals).unwrap()
-    );
+    match ResearchTree::load(Path::new("assets/research.toml")) {
+        Ok(research) => {
+            if let Err(err) = world.set_research(research) {
+                eprintln!("{}", err);
+            }
+        }
+        Err(err) => eprintln!("{}", err),
+    }
+    Some(world)
+}
 
-    let mediator: BasicMediator<TickResult> = BasicMediator::<TickResult>::builder().build();
-    mediator.send(BuildingTickRequest {
-        planet: &mut planet_1,
-    });
+fn main() {
+    let save_path = Path::new(SAVE_PATH);
+    let world = if save_path.exists() {
+        // a save that can't be read is left alone rather than overwritten by a new game
+        match load_world(save_path) {
+            Ok(world) => Some(world),
+            Err(err) => {
+                eprintln!("{}", err);
+                None
+            }
+        }
+    } else {
+        new_world()
+    };
+    let Some(mut world) = world else {
+        return;
+    };
 
-    println!(
-        "{:?}",
-        planet_1.get_resource(ResourceType::Minerals).unwrap()
-    );
+    println!("Type /help to see the commands");
+    let mut gateway = ConsoleGateway {
+        lines: io::stdin().lock().lines(),
+    };
+    while let Some(message) = gateway.receive() {
+        let reply = handle_message(&mut world, &message);
+        gateway.send(OutgoingMessage {
+            channel: message.channel,
+            content: reply.content,
+            embeds: reply.embeds,
+        });
+        if let Err(err) = save_world(&world, save_path) {
+            eprintln!("{}", err);
+        }
+    }
 }